@@ -0,0 +1,747 @@
+use std::fmt;
+
+use crate::source::{Location, Span};
+
+mod shorthand;
+mod tokenizer;
+
+pub use self::tokenizer::{tokenize, Token, Tokenizer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    // Where in the source the error was detected.
+    pub location: Location,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub struct StyleSheet {
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub selectors: Vec<Selector>,
+    pub declarations: Vec<Declaration>,
+    // From the first selector to the closing brace.
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum Selector {
+    Simple(SimpleSelector),
+    // Matches the elements matching the simple selector on the right, related
+    // by the combinator to an element matching the selector on the left.
+    Complex(Box<Selector>, Combinator, SimpleSelector),
+}
+
+// All the conditions must hold, a selector without any (`*`) matches all elements.
+#[derive(Debug)]
+pub struct SimpleSelector {
+    pub tag_name: Option<String>,
+    pub id: Option<String>,
+    pub classes:Vec<String>,
+}
+
+// Numbers of id, class and type selectors, compared in this order, see
+// https://www.w3.org/TR/selectors-3/#specificity
+pub type Specificity = (usize, usize, usize);
+
+impl Selector {
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Selector::Simple(simple) => simple.specificity(),
+            Selector::Complex(left, _, right) => {
+                let (a, b, c) = left.specificity();
+                let (d, e, f) = right.specificity();
+                (a + d, b + e, c + f)
+            },
+        }
+    }
+}
+
+impl SimpleSelector {
+    pub fn specificity(&self) -> Specificity {
+        (self.id.iter().count(), self.classes.len(), self.tag_name.iter().count())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combinator {
+    // `a b`
+    Descendant,
+    // `a > b`
+    Child,
+    // `a + b`
+    NextSibling,
+    // `a ~ b`
+    SubsequentSibling,
+}
+
+#[derive(Debug)]
+pub struct Declaration {
+    pub name: String,
+    pub value: Value,
+    // The value as parsed, for properties needing more than `value` tells.
+    pub components: Vec<ComponentValue>,
+    // Set by a trailing `!important`.
+    pub important: bool,
+    // From the name to the end of the value, without the semicolon.
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Keyword(String),
+    // Quoted string, with its escapes resolved.
+    String(String),
+    // Number with a unit, a length or e.g. an angle.
+    Length(f32, Unit),
+    Number(f32),
+    Percentage(f32),
+    Color(Color),
+    // Function with its comma-separated arguments, e.g. `rgb(0, 0, 0)`. An
+    // unquoted url is a `url` function taking a string.
+    Function(String, Vec<Value>),
+    // Values separated by spaces, commas or slashes, e.g. `0 auto`. Slashes bind
+    // tighter than spaces, which bind tighter than commas.
+    List(Vec<Value>, Separator),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Separator {
+    Space,
+    Comma,
+    Slash,
+}
+
+// Token, block or function of a value, see
+// https://www.w3.org/TR/css-syntax-3/#component-value
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComponentValue {
+    Token(Token),
+    // Block with the token opening it, '(', '[' or '{'.
+    Block(Token, Vec<ComponentValue>),
+    Function(String, Vec<ComponentValue>),
+}
+
+impl Value {
+    pub fn to_px(&self) -> f32 {
+        match self {
+            Value::Length(x, unit) => match unit.px() {
+                Some(px) => x * px,
+                //TODO relative lengths need the font size and the viewport
+                None if unit.is_length() => *x,
+                None => 0.0,
+            },
+            _ => 0.0,
+        }
+    }
+}
+
+// Unit of a dimension. Other units than lengths and angles, e.g. of times or
+// resolutions, are kept lowercased as written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unit {
+    Px,
+    Cm,
+    Mm,
+    Q,
+    In,
+    Pt,
+    Pc,
+    Em,
+    Rem,
+    Ex,
+    Ch,
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
+    Deg,
+    Grad,
+    Rad,
+    Turn,
+    Other(String),
+}
+
+impl Unit {
+    // Units are ASCII case-insensitive.
+    fn parse(unit: &str) -> Unit {
+        match unit.to_ascii_lowercase().as_str() {
+            "px" => Unit::Px,
+            "cm" => Unit::Cm,
+            "mm" => Unit::Mm,
+            "q" => Unit::Q,
+            "in" => Unit::In,
+            "pt" => Unit::Pt,
+            "pc" => Unit::Pc,
+            "em" => Unit::Em,
+            "rem" => Unit::Rem,
+            "ex" => Unit::Ex,
+            "ch" => Unit::Ch,
+            "vw" => Unit::Vw,
+            "vh" => Unit::Vh,
+            "vmin" => Unit::Vmin,
+            "vmax" => Unit::Vmax,
+            "deg" => Unit::Deg,
+            "grad" => Unit::Grad,
+            "rad" => Unit::Rad,
+            "turn" => Unit::Turn,
+            other => Unit::Other(other.to_string()),
+        }
+    }
+
+    pub fn is_length(&self) -> bool {
+        !matches!(self, Unit::Deg | Unit::Grad | Unit::Rad | Unit::Turn | Unit::Other(_))
+    }
+
+    // Pixels in one unit of an absolute length, with 96 pixels per inch.
+    fn px(&self) -> Option<f32> {
+        Some(match self {
+            Unit::Px => 1.0,
+            Unit::Cm => 96.0 / 2.54,
+            Unit::Mm => 96.0 / 25.4,
+            Unit::Q => 96.0 / 101.6,
+            Unit::In => 96.0,
+            Unit::Pt => 96.0 / 72.0,
+            Unit::Pc => 16.0,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+// Parser following the CSS Syntax Module Level 3, see
+// https://www.w3.org/TR/css-syntax-3/#parsing
+// Invalid rules and declarations are dropped and reported, the rest of the
+// style sheet is still parsed.
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    end: Location,
+    errors: Vec<ParseError>,
+}
+
+impl Parser {
+    fn new(mut tokenizer: Tokenizer) -> Parser {
+        let mut tokens = Vec::new();
+        while let Some(token) = tokenizer.next() {
+            tokens.push((token, tokenizer.span()));
+        }
+        Parser { tokens, pos: 0, end: tokenizer.end(), errors: tokenizer.take_errors() }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek()?.clone();
+        self.pos += 1;
+        Some(token)
+    }
+
+    // Start of the next token.
+    fn location(&self) -> Location {
+        self.tokens.get(self.pos).map_or(self.end, |(_, span)| span.start)
+    }
+
+    // End of the last consumed token.
+    fn last_end(&self) -> Location {
+        self.pos.checked_sub(1).map_or(self.end, |last| self.tokens[last].1.end)
+    }
+
+    fn error(&mut self, location: Location, message: &str) {
+        self.errors.push(ParseError{location, message: message.to_string()});
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek() == Some(&Token::Whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Consumes a token, or a whole block or function with its content.
+    fn skip_component_value(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token {
+                Token::OpenCurly | Token::OpenSquare | Token::OpenParen | Token::Function(_) => depth += 1,
+                Token::CloseCurly | Token::CloseSquare | Token::CloseParen if depth > 0 => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    // Consumes component values up to one of `ends` outside of blocks, or to
+    // the end. The end token is not consumed.
+    fn consume_until(&mut self, ends: &[Token]) -> Vec<Token> {
+        let start = self.pos;
+        while self.peek().is_some_and(|token| !ends.contains(token)) {
+            self.skip_component_value();
+        }
+        self.tokens[start..self.pos].iter().map(|(token, _)| token.clone()).collect()
+    }
+
+    fn parse_rules(&mut self) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return rules,
+                Some(Token::Cdo) | Some(Token::Cdc) => self.pos += 1,
+                Some(Token::AtKeyword(_)) => self.skip_at_rule(),
+                Some(_) => rules.extend(self.parse_rule()),
+            }
+        }
+    }
+
+    fn skip_at_rule(&mut self) {
+        let location = self.location();
+        if let Some(Token::AtKeyword(name)) = self.next() {
+            self.error(location, &format!("ignoring unsupported at-rule @{}", name));
+        }
+        self.consume_until(&[Token::Semicolon, Token::OpenCurly]);
+        if self.peek() == Some(&Token::OpenCurly) {
+            self.skip_component_value();
+        } else {
+            self.next();
+        }
+    }
+
+    // Qualified rule, dropped if its selectors are invalid.
+    fn parse_rule(&mut self) -> Option<Rule> {
+        let start = self.location();
+        let prelude = self.consume_until(&[Token::OpenCurly]);
+        if self.next().is_none() {
+            self.error(self.end, "unexpected end of style sheet before a declaration block");
+            return None;
+        }
+        let declarations = self.parse_declarations(true);
+        if self.next().is_none() {
+            self.error(self.end, "unclosed declaration block");
+        }
+        let span = Span { start, end: self.last_end() };
+        match parse_selector_tokens(&prelude) {
+            Some(selectors) => Some(Rule{selectors, declarations, span}),
+            None => {
+                self.error(start, "invalid selector, ignoring the rule");
+                None
+            },
+        }
+    }
+
+    // Declarations up to the end, or to the '}' closing the block if `in_block`.
+    fn parse_declarations(&mut self, in_block: bool) -> Vec<Declaration> {
+        let mut declarations = Vec::new();
+        loop {
+            match self.peek() {
+                None => return declarations,
+                Some(Token::CloseCurly) if in_block => return declarations,
+                Some(Token::Whitespace) | Some(Token::Semicolon) => self.pos += 1,
+                Some(Token::AtKeyword(_)) => self.skip_at_rule(),
+                Some(Token::Ident(_)) => declarations.extend(self.parse_declaration()),
+                Some(_) => {
+                    let location = self.location();
+                    self.error(location, "expected a declaration");
+                    self.consume_until(&[Token::Semicolon, Token::CloseCurly]);
+                },
+            }
+        }
+    }
+
+    // Declaration up to the next ';' or '}', dropped if it is invalid.
+    // Shorthands are expanded into their longhands.
+    fn parse_declaration(&mut self) -> Vec<Declaration> {
+        let start = self.location();
+        let name = match self.next() {
+            Some(Token::Ident(name)) => name,
+            _ => unreachable!(),
+        };
+        self.skip_whitespace();
+        if self.peek() != Some(&Token::Colon) {
+            let location = self.location();
+            self.error(location, &format!("expected ':' after property name {}", name));
+            self.consume_until(&[Token::Semicolon, Token::CloseCurly]);
+            return Vec::new();
+        }
+        self.pos += 1;
+        let value_start = self.pos;
+        let mut value = self.consume_until(&[Token::Semicolon, Token::CloseCurly]);
+        // The span ends with the last token of the value which is not whitespace.
+        let trailing_whitespace = value.iter().rev().take_while(|token| **token == Token::Whitespace).count();
+        value.truncate(value.len() - trailing_whitespace);
+        let end = match self.pos - trailing_whitespace {
+            end if end > value_start => self.tokens[end - 1].1.end,
+            _ => self.tokens[value_start - 1].1.end,
+        };
+        let span = Span { start, end };
+        // A trailing `!important`.
+        let mut important = false;
+        if let Some(Token::Ident(last)) = value.last() {
+            if last.eq_ignore_ascii_case("important") {
+                let bang = value[..value.len() - 1].iter().rposition(|token| *token != Token::Whitespace);
+                if let Some(bang) = bang.filter(|&bang| value[bang] == Token::Delim('!')) {
+                    value.truncate(bang);
+                    important = true;
+                }
+            }
+        }
+        // Whitespace around the value is not part of it.
+        while value.last() == Some(&Token::Whitespace) {
+            value.pop();
+        }
+        let leading_whitespace = value.iter().take_while(|token| **token == Token::Whitespace).count();
+        let components = parse_component_values(&value[leading_whitespace..]);
+        let declaration = parse_value(&components).map(|value| Declaration{name: name.clone(), value, components, important, span});
+        match declaration.and_then(shorthand::expand) {
+            Some(declarations) => declarations,
+            None => {
+                self.error(start, &format!("invalid value for property {}, ignoring the declaration", name));
+                Vec::new()
+            },
+        }
+    }
+}
+
+// Groups the blocks and functions of a list of tokens, see
+// https://www.w3.org/TR/css-syntax-3/#consume-component-value
+fn parse_component_values(tokens: &[Token]) -> Vec<ComponentValue> {
+    let mut pos = 0;
+    let mut components = Vec::new();
+    while pos < tokens.len() {
+        components.push(consume_component_value(tokens, &mut pos));
+    }
+    components
+}
+
+fn consume_component_value(tokens: &[Token], pos: &mut usize) -> ComponentValue {
+    let token = tokens[*pos].clone();
+    *pos += 1;
+    let close = match token {
+        Token::OpenCurly => Token::CloseCurly,
+        Token::OpenSquare => Token::CloseSquare,
+        Token::OpenParen | Token::Function(_) => Token::CloseParen,
+        _ => return ComponentValue::Token(token),
+    };
+    let mut content = Vec::new();
+    // An unclosed block ends with the tokens.
+    while *pos < tokens.len() {
+        if tokens[*pos] == close {
+            *pos += 1;
+            break;
+        }
+        content.push(consume_component_value(tokens, pos));
+    }
+    match token {
+        Token::Function(name) => ComponentValue::Function(name, content),
+        _ => ComponentValue::Block(token, content),
+    }
+}
+
+// Returns None for values which are not supported, like blocks.
+fn parse_value(components: &[ComponentValue]) -> Option<Value> {
+    let comma = ComponentValue::Token(Token::Comma);
+    if !components.contains(&comma) {
+        return parse_space_separated(components);
+    }
+    let items = components.split(|component| *component == comma)
+                          .map(parse_space_separated)
+                          .collect::<Option<Vec<Value>>>()?;
+    Some(Value::List(items, Separator::Comma))
+}
+
+fn parse_space_separated(components: &[ComponentValue]) -> Option<Value> {
+    // Values separated by slashes are grouped first.
+    let mut groups: Vec<Vec<Value>> = Vec::new();
+    let mut after_slash = false;
+    for component in components {
+        match component {
+            ComponentValue::Token(Token::Whitespace) => (),
+            ComponentValue::Token(Token::Delim('/')) => {
+                if groups.is_empty() || after_slash {
+                    return None;
+                }
+                after_slash = true;
+            },
+            _ => {
+                let value = parse_component_value(component)?;
+                match groups.last_mut() {
+                    Some(group) if after_slash => group.push(value),
+                    _ => groups.push(vec![value]),
+                }
+                after_slash = false;
+            },
+        }
+    }
+    if after_slash {
+        return None;
+    }
+    let mut values: Vec<Value> = groups.into_iter()
+                                       .map(|mut group| match group.len() {
+                                           1 => group.pop().unwrap(),
+                                           _ => Value::List(group, Separator::Slash),
+                                       })
+                                       .collect();
+    match values.len() {
+        0 => None,
+        1 => values.pop(),
+        _ => Some(Value::List(values, Separator::Space)),
+    }
+}
+
+fn parse_component_value(component: &ComponentValue) -> Option<Value> {
+    Some(match component {
+        ComponentValue::Token(Token::Ident(keyword)) => Value::Keyword(keyword.clone()),
+        ComponentValue::Token(Token::String(string)) => Value::String(string.clone()),
+        ComponentValue::Token(Token::Hash(hex, _)) => Value::Color(parse_color(hex)?),
+        ComponentValue::Token(Token::Dimension(number, unit)) => Value::Length(*number, Unit::parse(unit)),
+        ComponentValue::Token(Token::Number(number)) => Value::Number(*number),
+        ComponentValue::Token(Token::Percentage(number)) => Value::Percentage(*number),
+        ComponentValue::Token(Token::Url(url)) => Value::Function("url".to_string(), vec![Value::String(url.clone())]),
+        ComponentValue::Function(name, arguments) => {
+            let arguments = if arguments.iter().all(|component| *component == ComponentValue::Token(Token::Whitespace)) {
+                Vec::new()
+            } else {
+                match parse_value(arguments)? {
+                    Value::List(arguments, Separator::Comma) => arguments,
+                    argument => vec![argument],
+                }
+            };
+            Value::Function(name.clone(), arguments)
+        },
+        _ => return None,
+    })
+}
+
+// Color of a `#rgb` or `#rrggbb` hash.
+fn parse_color(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        3 => {
+            let doubled: String = hex.chars().flat_map(|c| [c, c]).collect();
+            parse_color(&doubled)
+        },
+        6 => Some(Color{r: channel(&hex[0..2])?, g: channel(&hex[2..4])?, b: channel(&hex[4..6])?}),
+        _ => None,
+    }
+}
+
+// Parses tag name, id and classes, e.g. `p#intro.note`. Returns None if there are none.
+fn parse_simple_selector(tokens: &[Token], pos: &mut usize) -> Option<SimpleSelector> {
+    let mut selector = SimpleSelector{tag_name: None, id: None, classes: Vec::new()};
+    let start = *pos;
+    match tokens.get(*pos) {
+        Some(Token::Delim('*')) => *pos += 1,
+        // Kept as written, as the tag names of foreign elements are case-sensitive.
+        Some(Token::Ident(name)) => {
+            selector.tag_name = Some(name.clone());
+            *pos += 1;
+        },
+        _ => (),
+    }
+    loop {
+        match (tokens.get(*pos), tokens.get(*pos + 1)) {
+            (Some(Token::Hash(id, true)), _) => {
+                selector.id = Some(id.clone());
+                *pos += 1;
+            },
+            (Some(Token::Delim('.')), Some(Token::Ident(class))) => {
+                selector.classes.push(class.clone());
+                *pos += 2;
+            },
+            _ => break,
+        }
+    }
+    if *pos == start { None } else { Some(selector) }
+}
+
+// Parses simple selectors separated by combinators. Returns None if the
+// selector is invalid.
+fn parse_selector(tokens: &[Token]) -> Option<Selector> {
+    let mut pos = 0;
+    let skip_whitespace = |pos: &mut usize| {
+        while tokens.get(*pos) == Some(&Token::Whitespace) {
+            *pos += 1;
+        }
+    };
+    skip_whitespace(&mut pos);
+    let mut selector = Selector::Simple(parse_simple_selector(tokens, &mut pos)?);
+    loop {
+        let before_whitespace = pos;
+        skip_whitespace(&mut pos);
+        let combinator = match tokens.get(pos) {
+            None => return Some(selector),
+            Some(Token::Delim('>')) => Combinator::Child,
+            Some(Token::Delim('+')) => Combinator::NextSibling,
+            Some(Token::Delim('~')) => Combinator::SubsequentSibling,
+            _ if pos > before_whitespace => Combinator::Descendant,
+            _ => return None,
+        };
+        if combinator != Combinator::Descendant {
+            pos += 1;
+            skip_whitespace(&mut pos);
+        }
+        let right = parse_simple_selector(tokens, &mut pos)?;
+        selector = Selector::Complex(Box::new(selector), combinator, right);
+    }
+}
+
+// Parses comma-separated selectors. Returns None if one of them is invalid.
+fn parse_selector_tokens(tokens: &[Token]) -> Option<Vec<Selector>> {
+    tokens.split(|token| *token == Token::Comma).map(parse_selector).collect()
+}
+
+// Parses a comma-separated list of selectors, like the argument of
+// querySelector. Returns None if one of them is invalid.
+pub fn parse_selector_list(source: &str) -> Option<Vec<Selector>> {
+    let (tokens, errors) = tokenize(source);
+    if !errors.is_empty() {
+        return None;
+    }
+    parse_selector_tokens(&tokens)
+}
+
+// Parses a declaration block without its braces, like the content of a style attribute.
+pub fn parse_declaration_list(source: &str) -> (Vec<Declaration>, Vec<ParseError>) {
+    let mut parser = Parser::new(Tokenizer::new(source));
+    let declarations = parser.parse_declarations(false);
+    (declarations, parser.errors)
+}
+
+// Parses a style sheet. Invalid rules and declarations are left out and
+// reported in the returned list of errors.
+pub fn parse(source: &str) -> (StyleSheet, Vec<ParseError>) {
+    parse_at(source, Location::start())
+}
+
+// Parses a style sheet found at `start` in a larger file.
+pub fn parse_at(source: &str, start: Location) -> (StyleSheet, Vec<ParseError>) {
+    let mut parser = Parser::new(Tokenizer::starting_at(source, start));
+    let rules = parser.parse_rules();
+    (StyleSheet{rules}, parser.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_declaration_list, Color, Separator, Unit, Value};
+
+    fn keyword(keyword: &str) -> Value {
+        Value::Keyword(keyword.to_string())
+    }
+
+    fn string(string: &str) -> Value {
+        Value::String(string.to_string())
+    }
+
+    fn function(name: &str, arguments: Vec<Value>) -> Value {
+        Value::Function(name.to_string(), arguments)
+    }
+
+    // Value of the only declaration in `source`, or None if it is invalid.
+    fn value_of(source: &str) -> Option<Value> {
+        let (mut declarations, _) = parse_declaration_list(source);
+        assert!(declarations.len() <= 1, "{}", source);
+        declarations.pop().map(|declaration| declaration.value)
+    }
+
+    #[test]
+    fn dimensions_keep_their_unit() {
+        assert_eq!(value_of("width: 12px"), Some(Value::Length(12.0, Unit::Px)));
+        assert_eq!(value_of("width: 1.5EM"), Some(Value::Length(1.5, Unit::Em)));
+        assert_eq!(value_of("width: 2rem"), Some(Value::Length(2.0, Unit::Rem)));
+        assert_eq!(value_of("width: 50%"), Some(Value::Percentage(50.0)));
+        assert_eq!(value_of("rotate: -90deg"), Some(Value::Length(-90.0, Unit::Deg)));
+        assert_eq!(value_of("transition-duration: 2S"), Some(Value::Length(2.0, Unit::Other("s".to_string()))));
+        assert_eq!(Value::Length(1.0, Unit::In).to_px(), 96.0);
+        assert_eq!(Value::Length(12.0, Unit::Pt).to_px(), 16.0);
+        assert_eq!(Value::Length(90.0, Unit::Deg).to_px(), 0.0);
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(value_of("grid-template-columns: 0 auto"),
+                   Some(Value::List(vec![Value::Number(0.0), keyword("auto")], Separator::Space)));
+        assert_eq!(value_of("font-family: a b, \"c\""),
+                   Some(Value::List(vec![Value::List(vec![keyword("a"), keyword("b")], Separator::Space), string("c")],
+                                    Separator::Comma)));
+        // Slashes bind tighter than spaces, which bind tighter than commas.
+        assert_eq!(value_of("grid-area: 1 / 2 3/4, 5"),
+                   Some(Value::List(vec![
+                       Value::List(vec![
+                           Value::List(vec![Value::Number(1.0), Value::Number(2.0)], Separator::Slash),
+                           Value::List(vec![Value::Number(3.0), Value::Number(4.0)], Separator::Slash),
+                       ], Separator::Space),
+                       Value::Number(5.0),
+                   ], Separator::Comma)));
+        assert_eq!(value_of("grid-area: / 1"), None);
+        assert_eq!(value_of("grid-area: 1 /"), None);
+        assert_eq!(value_of("grid-area: 1 / / 2"), None);
+        assert_eq!(value_of("font-family: a, , b"), None);
+        assert_eq!(value_of("font-family: a,"), None);
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(value_of("color: rgb(1, 2,3)"),
+                   Some(function("rgb", vec![Value::Number(1.0), Value::Number(2.0), Value::Number(3.0)])));
+        assert_eq!(value_of("transform: translate(1px, -2em) rotate(45deg)"),
+                   Some(Value::List(vec![
+                       function("translate", vec![Value::Length(1.0, Unit::Px), Value::Length(-2.0, Unit::Em)]),
+                       function("rotate", vec![Value::Length(45.0, Unit::Deg)]),
+                   ], Separator::Space)));
+        assert_eq!(value_of("background-image: linear-gradient(to right, #fff, rgba(0, 0, 0, 0.5))"),
+                   Some(function("linear-gradient", vec![
+                       Value::List(vec![keyword("to"), keyword("right")], Separator::Space),
+                       Value::Color(Color{r: 255, g: 255, b: 255}),
+                       function("rgba", vec![Value::Number(0.0), Value::Number(0.0), Value::Number(0.0), Value::Number(0.5)]),
+                   ])));
+        assert_eq!(value_of("content: counter( )"), Some(function("counter", Vec::new())));
+        // Unquoted and quoted urls are both url functions taking a string.
+        assert_eq!(value_of("background-image: url( a.png )"), Some(function("url", vec![string("a.png")])));
+        assert_eq!(value_of("background-image: url('a b.png')"), Some(function("url", vec![string("a b.png")])));
+        // An unclosed function ends with the value.
+        assert_eq!(value_of("width: calc(1px"), Some(function("calc", vec![Value::Length(1.0, Unit::Px)])));
+        // Blocks are not supported.
+        assert_eq!(value_of("grid-template-areas: [a]"), None);
+        assert_eq!(value_of("width: f([a])"), None);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(value_of("content: \"a 'b'\""), Some(string("a 'b'")));
+        assert_eq!(value_of("content: 'a \\'b\\' \\\"c\\\"'"), Some(string("a 'b' \"c\"")));
+        assert_eq!(value_of("content: \"\\41 \\2022\\\nb\""), Some(string("A\u{2022}b")));
+        assert_eq!(value_of("content: \"a\" 'b'"), Some(Value::List(vec![string("a"), string("b")], Separator::Space)));
+        // A string ends at the end of the value, but not at a newline.
+        assert_eq!(value_of("content: \"a"), Some(string("a")));
+        assert_eq!(value_of("content: \"a\nb\""), None);
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(value_of("color: #0a0B0c"), Some(Value::Color(Color{r: 10, g: 11, b: 12})));
+        assert_eq!(value_of("color: #abc"), Some(Value::Color(Color{r: 0xaa, g: 0xbb, b: 0xcc})));
+        assert_eq!(value_of("color: #abcd"), None);
+        assert_eq!(value_of("color: #ggg"), None);
+    }
+}
@@ -0,0 +1,556 @@
+use crate::css::{ComponentValue, Declaration, Separator, Token, Value};
+
+// Expansion of shorthand properties into their longhands, e.g. `margin: 0 auto`
+// into `margin-top: 0`, `margin-right: auto`, etc. Longhands left out of a
+// shorthand are set to their initial value, as the shorthand resets them.
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+// Keywords valid for any property, which a shorthand gives to all its longhands.
+const CSS_WIDE_KEYWORDS: &[&str] = &["inherit", "initial", "unset", "revert"];
+
+const BORDER_STYLES: &[&str] = &[
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+const BORDER_WIDTHS: &[&str] = &["thin", "medium", "thick"];
+
+const FONT_SIZES: &[&str] = &[
+    "xx-small", "x-small", "small", "medium", "large", "x-large", "xx-large", "xxx-large",
+    "larger", "smaller",
+];
+
+const FONT_STRETCHES: &[&str] = &[
+    "ultra-condensed", "extra-condensed", "condensed", "semi-condensed", "semi-expanded",
+    "expanded", "extra-expanded", "ultra-expanded",
+];
+
+const BACKGROUND_REPEATS: &[&str] = &["repeat", "repeat-x", "repeat-y", "no-repeat", "space", "round"];
+
+const BACKGROUND_ATTACHMENTS: &[&str] = &["scroll", "fixed", "local"];
+
+const POSITIONS: &[&str] = &["left", "right", "top", "bottom", "center"];
+
+// Named colors of CSS Color Level 4, see https://www.w3.org/TR/css-color-4/#named-colors
+const NAMED_COLORS: &[&str] = &[
+    "aliceblue", "antiquewhite", "aqua", "aquamarine", "azure", "beige", "bisque", "black",
+    "blanchedalmond", "blue", "blueviolet", "brown", "burlywood", "cadetblue", "chartreuse",
+    "chocolate", "coral", "cornflowerblue", "cornsilk", "crimson", "cyan", "darkblue", "darkcyan",
+    "darkgoldenrod", "darkgray", "darkgreen", "darkgrey", "darkkhaki", "darkmagenta",
+    "darkolivegreen", "darkorange", "darkorchid", "darkred", "darksalmon", "darkseagreen",
+    "darkslateblue", "darkslategray", "darkslategrey", "darkturquoise", "darkviolet", "deeppink",
+    "deepskyblue", "dimgray", "dimgrey", "dodgerblue", "firebrick", "floralwhite", "forestgreen",
+    "fuchsia", "gainsboro", "ghostwhite", "gold", "goldenrod", "gray", "green", "greenyellow",
+    "grey", "honeydew", "hotpink", "indianred", "indigo", "ivory", "khaki", "lavender",
+    "lavenderblush", "lawngreen", "lemonchiffon", "lightblue", "lightcoral", "lightcyan",
+    "lightgoldenrodyellow", "lightgray", "lightgreen", "lightgrey", "lightpink", "lightsalmon",
+    "lightseagreen", "lightskyblue", "lightslategray", "lightslategrey", "lightsteelblue",
+    "lightyellow", "lime", "limegreen", "linen", "magenta", "maroon", "mediumaquamarine",
+    "mediumblue", "mediumorchid", "mediumpurple", "mediumseagreen", "mediumslateblue",
+    "mediumspringgreen", "mediumturquoise", "mediumvioletred", "midnightblue", "mintcream",
+    "mistyrose", "moccasin", "navajowhite", "navy", "oldlace", "olive", "olivedrab", "orange",
+    "orangered", "orchid", "palegoldenrod", "palegreen", "paleturquoise", "palevioletred",
+    "papayawhip", "peachpuff", "peru", "pink", "plum", "powderblue", "purple", "rebeccapurple",
+    "red", "rosybrown", "royalblue", "saddlebrown", "salmon", "sandybrown", "seagreen", "seashell",
+    "sienna", "silver", "skyblue", "slateblue", "slategray", "slategrey", "snow", "springgreen",
+    "steelblue", "tan", "teal", "thistle", "tomato", "turquoise", "violet", "wheat", "white",
+    "whitesmoke", "yellow", "yellowgreen",
+];
+
+// Longhands of a shorthand property, in the order their values are expanded.
+fn longhands(shorthand: &str) -> Option<Vec<String>> {
+    let sides = |pattern: &str| SIDES.iter().map(|side| pattern.replace("{}", side)).collect();
+    Some(match shorthand {
+        "margin" => sides("margin-{}"),
+        "padding" => sides("padding-{}"),
+        "border-width" => sides("border-{}-width"),
+        "border-style" => sides("border-{}-style"),
+        "border-color" => sides("border-{}-color"),
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            ["width", "style", "color"].iter().map(|part| format!("{}-{}", shorthand, part)).collect()
+        },
+        "border" => SIDES.iter()
+                         .flat_map(|side| ["width", "style", "color"].iter().map(move |part| format!("border-{}-{}", side, part)))
+                         .collect(),
+        "font" => ["font-style", "font-variant", "font-weight", "font-stretch", "font-size", "line-height", "font-family"]
+                      .iter().map(|name| name.to_string()).collect(),
+        "background" => ["background-color", "background-image", "background-repeat", "background-attachment",
+                         "background-position"]
+                            .iter().map(|name| name.to_string()).collect(),
+        "list-style" => ["list-style-type", "list-style-position", "list-style-image"]
+                            .iter().map(|name| name.to_string()).collect(),
+        _ => return None,
+    })
+}
+
+// Value of a longhand with the component values it is parsed from: a part of
+// the shorthand, or the initial value of a longhand left out of it.
+#[derive(Clone)]
+struct Part {
+    value: Value,
+    components: Vec<ComponentValue>,
+}
+
+impl Part {
+    fn new(value: &Value, components: &[ComponentValue]) -> Part {
+        Part { value: value.clone(), components: components.to_vec() }
+    }
+
+    fn keyword(keyword: &str) -> Part {
+        Part {
+            value: Value::Keyword(keyword.to_string()),
+            components: vec![ComponentValue::Token(Token::Ident(keyword.to_string()))],
+        }
+    }
+
+    fn percentage(number: f32) -> Part {
+        Part { value: Value::Percentage(number), components: vec![ComponentValue::Token(Token::Percentage(number))] }
+    }
+
+    // Parts separated by `separator`, or the part itself.
+    fn list(mut parts: Vec<Part>, separator: Separator) -> Part {
+        if parts.len() == 1 {
+            return parts.pop().unwrap();
+        }
+        let token = match separator {
+            Separator::Space => Token::Whitespace,
+            Separator::Comma => Token::Comma,
+            Separator::Slash => Token::Delim('/'),
+        };
+        let mut components = Vec::new();
+        for part in &parts {
+            if !components.is_empty() {
+                components.push(ComponentValue::Token(token.clone()));
+            }
+            components.extend(part.components.iter().cloned());
+        }
+        Part { value: Value::List(parts.into_iter().map(|part| part.value).collect(), separator), components }
+    }
+}
+
+// Longhands of a shorthand declaration, or the declaration itself if it is
+// not a shorthand. Returns None if the value is invalid for the shorthand.
+pub(crate) fn expand(declaration: Declaration) -> Option<Vec<Declaration>> {
+    let shorthand = declaration.name.to_ascii_lowercase();
+    let longhands = match longhands(&shorthand) {
+        Some(longhands) => longhands,
+        None => return Some(vec![declaration]),
+    };
+    let whole = (&declaration.value, &declaration.components[..]);
+    let parts = match &declaration.value {
+        Value::Keyword(keyword) if CSS_WIDE_KEYWORDS.iter().any(|wide| keyword.eq_ignore_ascii_case(wide)) => {
+            vec![Part::new(whole.0, whole.1); longhands.len()]
+        },
+        _ => match shorthand.as_str() {
+            "margin" => four_sides(whole, |value| is_length(value) || is_keyword(value, &["auto"]))?,
+            "padding" => four_sides(whole, is_length)?,
+            "border-width" => four_sides(whole, is_border_width)?,
+            "border-style" => four_sides(whole, |value| is_keyword(value, BORDER_STYLES))?,
+            "border-color" => four_sides(whole, is_color)?,
+            "border" => border(whole)?.iter().cycle().take(12).cloned().collect(),
+            "font" => font(whole)?,
+            "background" => background(whole)?,
+            "list-style" => list_style(whole)?,
+            _ => border(whole)?,
+        },
+    };
+    Some(longhands.into_iter()
+                  .zip(parts)
+                  .map(|(name, part)| Declaration {
+                      name,
+                      value: part.value,
+                      components: part.components,
+                      important: declaration.important,
+                      span: declaration.span,
+                  })
+                  .collect())
+}
+
+fn trim_whitespace(mut components: &[ComponentValue]) -> &[ComponentValue] {
+    let whitespace = ComponentValue::Token(Token::Whitespace);
+    while let [first, rest @ ..] = components {
+        if *first != whitespace {
+            break;
+        }
+        components = rest;
+    }
+    while let [rest @ .., last] = components {
+        if *last != whitespace {
+            break;
+        }
+        components = rest;
+    }
+    components
+}
+
+// A value with the component values it is parsed from.
+type Parsed<'a> = (&'a Value, &'a [ComponentValue]);
+
+// Items of a list separated by `separator`, or the value itself, with the
+// component values of each. The components are split like parse_value
+// groups them.
+fn items<'a>((value, components): Parsed<'a>, separator: Separator) -> Vec<Parsed<'a>> {
+    let values = match value {
+        Value::List(values, list_separator) if *list_separator == separator => values,
+        value => return vec![(value, trim_whitespace(components))],
+    };
+    let groups: Vec<&[ComponentValue]> = match separator {
+        Separator::Comma => components.split(|component| *component == ComponentValue::Token(Token::Comma))
+                                      .map(trim_whitespace)
+                                      .collect(),
+        Separator::Slash => components.split(|component| *component == ComponentValue::Token(Token::Delim('/')))
+                                      .map(trim_whitespace)
+                                      .collect(),
+        // Values joined by a slash are a single item.
+        Separator::Space => {
+            let mut groups = Vec::new();
+            let mut start = None;
+            let mut after_slash = false;
+            for (index, component) in components.iter().enumerate() {
+                match component {
+                    ComponentValue::Token(Token::Whitespace) => (),
+                    ComponentValue::Token(Token::Delim('/')) => after_slash = true,
+                    _ => {
+                        if !after_slash {
+                            if let Some(start) = start.replace(index) {
+                                groups.push(trim_whitespace(&components[start..index]));
+                            }
+                        }
+                        after_slash = false;
+                    },
+                }
+            }
+            groups.extend(start.map(|start| trim_whitespace(&components[start..])));
+            groups
+        },
+    };
+    values.iter().zip(groups).collect()
+}
+
+fn is_keyword(value: &Value, keywords: &[&str]) -> bool {
+    matches!(value, Value::Keyword(keyword) if keywords.iter().any(|other| keyword.eq_ignore_ascii_case(other)))
+}
+
+// Length or percentage. A unitless zero is a length.
+fn is_length(value: &Value) -> bool {
+    match value {
+        Value::Length(_, unit) => unit.is_length(),
+        Value::Percentage(_) => true,
+        Value::Number(number) => *number == 0.0,
+        _ => false,
+    }
+}
+
+fn is_border_width(value: &Value) -> bool {
+    !matches!(value, Value::Percentage(_)) && is_length(value) || is_keyword(value, BORDER_WIDTHS)
+}
+
+fn is_color(value: &Value) -> bool {
+    match value {
+        Value::Color(_) => true,
+        Value::Keyword(_) => is_keyword(value, &["currentcolor", "transparent"]) || is_keyword(value, NAMED_COLORS),
+        Value::Function(name, _) => ["rgb", "rgba", "hsl", "hsla"].iter().any(|other| name.eq_ignore_ascii_case(other)),
+        _ => false,
+    }
+}
+
+fn is_image(value: &Value) -> bool {
+    match value {
+        Value::Function(name, _) => name.eq_ignore_ascii_case("url") || name.to_ascii_lowercase().ends_with("gradient"),
+        _ => false,
+    }
+}
+
+// Values of the top, right, bottom and left sides from one to four values:
+// top, right and bottom default to the opposite side, and right to top.
+fn four_sides(value: Parsed, valid: impl Fn(&Value) -> bool) -> Option<Vec<Part>> {
+    let values = items(value, Separator::Space);
+    if !values.iter().all(|(value, _)| valid(value)) {
+        return None;
+    }
+    let (top, right, bottom, left) = match values[..] {
+        [all] => (all, all, all, all),
+        [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+        [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+        [top, right, bottom, left] => (top, right, bottom, left),
+        _ => return None,
+    };
+    Some([top, right, bottom, left].iter().map(|(value, components)| Part::new(value, components)).collect())
+}
+
+// Width, style and color of a border, in any order.
+fn border(value: Parsed) -> Option<Vec<Part>> {
+    let (mut width, mut style, mut color) = (None, None, None);
+    for (value, components) in items(value, Separator::Space) {
+        // Style and width keywords are not colors.
+        let part = if is_keyword(value, BORDER_STYLES) {
+            &mut style
+        } else if is_border_width(value) {
+            &mut width
+        } else if is_color(value) {
+            &mut color
+        } else {
+            return None;
+        };
+        if part.replace(Part::new(value, components)).is_some() {
+            return None;
+        }
+    }
+    Some(vec![width.unwrap_or_else(|| Part::keyword("medium")),
+              style.unwrap_or_else(|| Part::keyword("none")),
+              color.unwrap_or_else(|| Part::keyword("currentcolor"))])
+}
+
+// `[style || variant || weight || stretch]? size [/ line-height]? family`,
+// where `normal` may stand for any of the first four.
+fn font(value: Parsed) -> Option<Vec<Part>> {
+    // Families after the first one are the next items of a comma-separated list.
+    let families = items(value, Separator::Comma);
+    let values = items(families[0], Separator::Space);
+    let (mut style, mut variant, mut weight, mut stretch) = (None, None, None, None);
+    let mut position = 0;
+    while let Some(&(value, components)) = values.get(position) {
+        let part = match value {
+            _ if is_keyword(value, &["normal"]) => None,
+            _ if is_keyword(value, &["italic", "oblique"]) => Some(&mut style),
+            _ if is_keyword(value, &["small-caps"]) => Some(&mut variant),
+            _ if is_keyword(value, &["bold", "bolder", "lighter"]) => Some(&mut weight),
+            Value::Number(number) if (1.0..=1000.0).contains(number) => Some(&mut weight),
+            _ if is_keyword(value, FONT_STRETCHES) => Some(&mut stretch),
+            _ => break,
+        };
+        if let Some(part) = part {
+            if part.replace(Part::new(value, components)).is_some() {
+                return None;
+            }
+        }
+        position += 1;
+    }
+    let (size, line_height) = match *values.get(position)? {
+        (Value::List(slashed, Separator::Slash), _) if slashed.len() == 2 => {
+            let slashed = items(values[position], Separator::Slash);
+            (slashed[0], Some(slashed[1]))
+        },
+        size => (size, None),
+    };
+    let valid_line_height = |(value, _): Parsed| is_length(value) || matches!(value, Value::Number(_)) || is_keyword(value, &["normal"]);
+    if !(is_length(size.0) || is_keyword(size.0, FONT_SIZES)) || !line_height.is_none_or(valid_line_height) {
+        return None;
+    }
+    // A family is a string, or keywords like `Times New Roman`.
+    let is_family = |value: &Value| match value {
+        Value::String(_) | Value::Keyword(_) => true,
+        Value::List(values, Separator::Space) => values.iter().all(|value| matches!(value, Value::Keyword(_))),
+        _ => false,
+    };
+    if values.len() == position + 1 {
+        return None;
+    }
+    let first_family = Part::list(values[position + 1..].iter().map(|(value, components)| Part::new(value, components)).collect(),
+                                  Separator::Space);
+    if !is_family(&first_family.value) || !families[1..].iter().all(|(value, _)| is_family(value)) {
+        return None;
+    }
+    let family = Part::list(std::iter::once(first_family)
+                                .chain(families[1..].iter().map(|(value, components)| Part::new(value, components)))
+                                .collect(),
+                            Separator::Comma);
+    let normal = || Part::keyword("normal");
+    Some(vec![style.unwrap_or_else(normal),
+              variant.unwrap_or_else(normal),
+              weight.unwrap_or_else(normal),
+              stretch.unwrap_or_else(normal),
+              Part::new(size.0, size.1),
+              line_height.map_or_else(normal, |(value, components)| Part::new(value, components)),
+              family])
+}
+
+// Layers separated by commas, each with an image, a repeat, an attachment and
+// a position in any order. Only the last layer may have a color.
+fn background(value: Parsed) -> Option<Vec<Part>> {
+    let layers = items(value, Separator::Comma);
+    let (mut images, mut repeats, mut attachments, mut positions) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut color = None;
+    for (index, &layer) in layers.iter().enumerate() {
+        let (mut image, mut attachment) = (None, None);
+        let (mut repeat, mut position) = (Vec::new(), Vec::new());
+        for (value, components) in items(layer, Separator::Space) {
+            let part = Part::new(value, components);
+            if (is_image(value) || is_keyword(value, &["none"])) && image.is_none() {
+                image = Some(part);
+            } else if is_keyword(value, BACKGROUND_REPEATS) && repeat.len() < 2 {
+                repeat.push(part);
+            } else if is_keyword(value, BACKGROUND_ATTACHMENTS) && attachment.is_none() {
+                attachment = Some(part);
+            } else if (is_length(value) || is_keyword(value, POSITIONS)) && position.len() < 4 {
+                position.push(part);
+            } else if is_color(value) && index == layers.len() - 1 && color.is_none() {
+                color = Some(part);
+            } else {
+                return None;
+            }
+        }
+        images.push(image.unwrap_or_else(|| Part::keyword("none")));
+        repeats.push(if repeat.is_empty() { Part::keyword("repeat") } else { Part::list(repeat, Separator::Space) });
+        attachments.push(attachment.unwrap_or_else(|| Part::keyword("scroll")));
+        positions.push(if position.is_empty() {
+            Part::list(vec![Part::percentage(0.0), Part::percentage(0.0)], Separator::Space)
+        } else {
+            Part::list(position, Separator::Space)
+        });
+    }
+    Some(vec![color.unwrap_or_else(|| Part::keyword("transparent")),
+              Part::list(images, Separator::Comma),
+              Part::list(repeats, Separator::Comma),
+              Part::list(attachments, Separator::Comma),
+              Part::list(positions, Separator::Comma)])
+}
+
+// Type, position and image in any order. `none` sets the type or the image,
+// whichever is not given otherwise.
+fn list_style(value: Parsed) -> Option<Vec<Part>> {
+    let (mut style_type, mut position, mut image) = (None, None, None);
+    let mut nones = Vec::new();
+    for (value, components) in items(value, Separator::Space) {
+        let part = match value {
+            _ if is_keyword(value, &["none"]) => {
+                nones.push(Part::new(value, components));
+                continue;
+            },
+            _ if is_keyword(value, &["inside", "outside"]) => &mut position,
+            _ if is_image(value) => &mut image,
+            Value::Keyword(_) | Value::String(_) => &mut style_type,
+            _ => return None,
+        };
+        if part.replace(Part::new(value, components)).is_some() {
+            return None;
+        }
+    }
+    let unset = [&style_type, &image].iter().filter(|part| part.is_none()).count();
+    if nones.len() > unset {
+        return None;
+    }
+    let mut nones = nones.into_iter();
+    let style_type = style_type.or_else(|| nones.next()).unwrap_or_else(|| Part::keyword("disc"));
+    let image = image.or_else(|| nones.next()).unwrap_or_else(|| Part::keyword("none"));
+    Some(vec![style_type, position.unwrap_or_else(|| Part::keyword("outside")), image])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::{parse_declaration_list, ComponentValue, Declaration, Value};
+
+    fn longhands(declarations: &[Declaration]) -> Vec<(String, Value, Vec<ComponentValue>)> {
+        declarations.iter()
+                    .map(|declaration| (declaration.name.clone(), declaration.value.clone(), declaration.components.clone()))
+                    .collect()
+    }
+
+    // Checks that `shorthand` expands to the same values and components as the
+    // `expected` longhands.
+    fn assert_expands(shorthand: &str, expected: &str) {
+        let (expanded, errors) = parse_declaration_list(shorthand);
+        assert_eq!(errors, [], "errors of {}", shorthand);
+        assert_eq!(longhands(&expanded), longhands(&parse_declaration_list(expected).0), "longhands of {}", shorthand);
+    }
+
+    fn assert_invalid(shorthand: &str) {
+        let (expanded, errors) = parse_declaration_list(shorthand);
+        assert_eq!(longhands(&expanded), [], "longhands of {}", shorthand);
+        assert_eq!(errors.len(), 1, "errors of {}", shorthand);
+    }
+
+    #[test]
+    fn one_to_four_sides() {
+        assert_expands("margin: 1px", "margin-top: 1px; margin-right: 1px; margin-bottom: 1px; margin-left: 1px");
+        assert_expands("margin: 1px auto", "margin-top: 1px; margin-right: auto; margin-bottom: 1px; margin-left: auto");
+        assert_expands("padding: 1px 2% 0", "padding-top: 1px; padding-right: 2%; padding-bottom: 0; padding-left: 2%");
+        assert_expands("border-width: thin 2px medium 0 !important",
+                       "border-top-width: thin !important; border-right-width: 2px !important; \
+                        border-bottom-width: medium !important; border-left-width: 0 !important");
+        assert_expands("border-color: red #000 rgb(1, 2, 3) transparent",
+                       "border-top-color: red; border-right-color: #000; border-bottom-color: rgb(1, 2, 3); \
+                        border-left-color: transparent");
+        assert_invalid("margin: 1px 2px 3px 4px 5px");
+        assert_invalid("padding: auto");
+        assert_invalid("border-style: solid soild");
+        assert_invalid("border-width: 10%");
+    }
+
+    #[test]
+    fn wide_keywords_go_to_all_the_longhands() {
+        assert_expands("margin: inherit", "margin-top: inherit; margin-right: inherit; margin-bottom: inherit; margin-left: inherit");
+        assert_expands("list-style: initial", "list-style-type: initial; list-style-position: initial; list-style-image: initial");
+    }
+
+    #[test]
+    fn borders() {
+        assert_expands("border-top: dashed 2px", "border-top-width: 2px; border-top-style: dashed; border-top-color: currentcolor");
+        assert_expands("border-left: CurrentColor", "border-left-width: medium; border-left-style: none; border-left-color: CurrentColor");
+        let sides = |width: &str, style: &str, color: &str| {
+            ["top", "right", "bottom", "left"].iter()
+                                              .map(|side| format!("border-{0}-width: {1}; border-{0}-style: {2}; border-{0}-color: {3}; ",
+                                                                  side, width, style, color))
+                                              .collect::<String>()
+        };
+        assert_expands("border: 1px solid rebeccapurple", &sides("1px", "solid", "rebeccapurple"));
+        assert_expands("border: none", &sides("medium", "none", "currentcolor"));
+        // Only colors are taken as the color.
+        assert_invalid("border: 1px soild");
+        assert_invalid("border: 1px solid red blue");
+        assert_invalid("border: 1px solid \"red\"");
+    }
+
+    #[test]
+    fn fonts() {
+        assert_expands("font: 12px serif",
+                       "font-style: normal; font-variant: normal; font-weight: normal; font-stretch: normal; \
+                        font-size: 12px; line-height: normal; font-family: serif");
+        assert_expands("font: italic bold condensed large/1.5 \"Helvetica Neue\",Times New Roman,serif",
+                       "font-style: italic; font-variant: normal; font-weight: bold; font-stretch: condensed; \
+                        font-size: large; line-height: 1.5; font-family: \"Helvetica Neue\",Times New Roman,serif");
+        assert_expands("font: normal small-caps 700 10% / 12px Times New Roman",
+                       "font-style: normal; font-variant: small-caps; font-weight: 700; font-stretch: normal; \
+                        font-size: 10%; line-height: 12px; font-family: Times New Roman");
+        assert_invalid("font: 12px");
+        assert_invalid("font: bold serif");
+        assert_invalid("font: italic italic 12px serif");
+        assert_invalid("font: 12px/auto serif");
+        assert_invalid("font: 12px serif, 1px");
+    }
+
+    #[test]
+    fn backgrounds() {
+        assert_expands("background: red",
+                       "background-color: red; background-image: none; background-repeat: repeat; \
+                        background-attachment: scroll; background-position: 0% 0%");
+        assert_expands("background: url(a.png) no-repeat fixed center 10px #fff",
+                       "background-color: #fff; background-image: url(a.png); background-repeat: no-repeat; \
+                        background-attachment: fixed; background-position: center 10px");
+        assert_expands("background: url(a.png) repeat-x, linear-gradient(red, blue) left top transparent",
+                       "background-color: transparent; background-image: url(a.png),linear-gradient(red, blue); \
+                        background-repeat: repeat-x,repeat; background-attachment: scroll,scroll; \
+                        background-position: 0% 0%,left top");
+        // Only the last layer has a color.
+        assert_invalid("background: red, url(a.png)");
+        assert_invalid("background: url(a.png) url(b.png)");
+        assert_invalid("background: bleu");
+    }
+
+    #[test]
+    fn list_styles() {
+        assert_expands("list-style: square inside",
+                       "list-style-type: square; list-style-position: inside; list-style-image: none");
+        assert_expands("list-style: url(a.png)",
+                       "list-style-type: disc; list-style-position: outside; list-style-image: url(a.png)");
+        assert_expands("list-style: none",
+                       "list-style-type: none; list-style-position: outside; list-style-image: none");
+        assert_expands("list-style: none url(a.png)",
+                       "list-style-type: none; list-style-position: outside; list-style-image: url(a.png)");
+        assert_expands("list-style: \"-\" none",
+                       "list-style-type: \"-\"; list-style-position: outside; list-style-image: none");
+        assert_invalid("list-style: none none none");
+        assert_invalid("list-style: inside outside");
+        assert_invalid("list-style: 1px");
+    }
+}
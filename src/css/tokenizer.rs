@@ -0,0 +1,549 @@
+use crate::css::ParseError;
+use crate::source::{LineIndex, Location, Span};
+
+// Tokenizer following the CSS Syntax Module Level 3:
+// https://www.w3.org/TR/css-syntax-3/#tokenization
+// Comments are dropped, escapes are resolved in names, strings and urls.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    // Name of a function, the '(' is part of the token.
+    Function(String),
+    AtKeyword(String),
+    // Hash with whether its value is a valid identifier, as ids must be.
+    Hash(String, bool),
+    String(String),
+    // String with an unescaped newline.
+    BadString,
+    // Unquoted `url(...)`.
+    Url(String),
+    BadUrl,
+    Delim(char),
+    Number(f32),
+    Percentage(f32),
+    Dimension(f32, String),
+    Whitespace,
+    // `<!--` and `-->`, ignored around rules for old style sheets hidden in comments.
+    Cdo,
+    Cdc,
+    Colon,
+    Semicolon,
+    Comma,
+    OpenSquare,
+    CloseSquare,
+    OpenParen,
+    CloseParen,
+    OpenCurly,
+    CloseCurly,
+}
+
+pub struct Tokenizer {
+    input: String,
+    lines: LineIndex,
+    // Location of the start of the input, for style sheets embedded in a document.
+    start: Location,
+    pos: usize,
+    last_span: Span,
+    errors: Vec<ParseError>,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_ident_char(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n'
+}
+
+fn is_valid_escape(first: Option<char>, second: Option<char>) -> bool {
+    first == Some('\\') && second != Some('\n')
+}
+
+fn would_start_ident(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    match first {
+        Some('-') => second.is_some_and(|c| is_ident_start(c) || c == '-') || is_valid_escape(second, third),
+        Some(c) if is_ident_start(c) => true,
+        Some('\\') => is_valid_escape(first, second),
+        _ => false,
+    }
+}
+
+fn would_start_number(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    match first {
+        Some('+') | Some('-') => {
+            second.is_some_and(|c| c.is_ascii_digit()) || second == Some('.') && third.is_some_and(|c| c.is_ascii_digit())
+        },
+        Some('.') => second.is_some_and(|c| c.is_ascii_digit()),
+        Some(c) => c.is_ascii_digit(),
+        None => false,
+    }
+}
+
+impl Tokenizer {
+    pub fn new(source: &str) -> Tokenizer {
+        Tokenizer::starting_at(source, Location::start())
+    }
+
+    // Tokenizer of a source found at `start` in a larger file.
+    pub fn starting_at(source: &str, start: Location) -> Tokenizer {
+        Tokenizer {
+            input: source.to_string(),
+            lines: LineIndex::new(source),
+            start,
+            pos: 0,
+            last_span: Span::at(start),
+            errors: Vec::new(),
+        }
+    }
+
+    // Part of the source the last returned token comes from.
+    pub fn span(&self) -> Span {
+        self.last_span
+    }
+
+    // Location of the end of the source.
+    pub fn end(&self) -> Location {
+        self.location(self.input.len())
+    }
+
+    fn location(&self, offset: usize) -> Location {
+        let location = self.lines.location(&self.input, offset);
+        Location {
+            offset: self.start.offset + location.offset,
+            line: self.start.line + location.line - 1,
+            column: if location.line == 1 { self.start.column + location.column - 1 } else { location.column },
+        }
+    }
+
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn error(&mut self, message: &str) {
+        let location = self.location(self.pos);
+        self.errors.push(ParseError{location, message: message.to_string()});
+    }
+
+    // Character at `pos`, with its length in the source. Newlines are
+    // normalized to '\n' and NUL is replaced.
+    fn char_at(&self, pos: usize) -> Option<(char, usize)> {
+        let c = self.input[pos..].chars().next()?;
+        Some(match c {
+            '\r' if self.input[pos + 1..].starts_with('\n') => ('\n', 2),
+            '\r' | '\x0c' => ('\n', 1),
+            '\0' => ('\u{fffd}', 1),
+            c => (c, c.len_utf8()),
+        })
+    }
+
+    // Character `n` positions after the next one.
+    fn peek(&self, n: usize) -> Option<char> {
+        let mut pos = self.pos;
+        for _ in 0..n {
+            pos += self.char_at(pos)?.1;
+        }
+        self.char_at(pos).map(|(c, _)| c)
+    }
+
+    fn consume(&mut self) -> Option<char> {
+        let (c, len) = self.char_at(self.pos)?;
+        self.pos += len;
+        Some(c)
+    }
+
+    fn consume_comments(&mut self) {
+        while self.input[self.pos..].starts_with("/*") {
+            match self.input[self.pos + 2..].find("*/") {
+                Some(end) => self.pos += end + 4,
+                None => {
+                    self.pos = self.input.len();
+                    self.error("unterminated comment");
+                },
+            }
+        }
+    }
+
+    // Consumes an escape, the backslash being already consumed.
+    fn consume_escape(&mut self) -> char {
+        match self.consume() {
+            Some(c) if c.is_ascii_hexdigit() => {
+                let mut hex = c.to_string();
+                while hex.len() < 6 && self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+                    hex.push(self.consume().unwrap());
+                }
+                if self.peek(0).is_some_and(is_whitespace) {
+                    self.consume();
+                }
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some('\0') | None => '\u{fffd}',
+                    Some(c) => c,
+                }
+            },
+            Some(c) => c,
+            None => {
+                self.error("unexpected end of file in escape");
+                '\u{fffd}'
+            },
+        }
+    }
+
+    fn consume_name(&mut self) -> String {
+        let mut name = String::new();
+        loop {
+            match self.peek(0) {
+                Some(c) if is_ident_char(c) => {
+                    self.consume();
+                    name.push(c);
+                },
+                Some('\\') if is_valid_escape(Some('\\'), self.peek(1)) => {
+                    self.consume();
+                    name.push(self.consume_escape());
+                },
+                _ => return name,
+            }
+        }
+    }
+
+    fn consume_number(&mut self) -> f32 {
+        let mut repr = String::new();
+        if let Some(sign @ ('+' | '-')) = self.peek(0) {
+            self.consume();
+            repr.push(sign);
+        }
+        self.consume_digits(&mut repr);
+        if self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+            repr.push(self.consume().unwrap());
+            self.consume_digits(&mut repr);
+        }
+        let exponent_digit = match (self.peek(1), self.peek(2)) {
+            (Some('+' | '-'), Some(c)) => c.is_ascii_digit(),
+            (Some(c), _) => c.is_ascii_digit(),
+            _ => false,
+        };
+        if matches!(self.peek(0), Some('e' | 'E')) && exponent_digit {
+            repr.push(self.consume().unwrap());
+            if let Some(sign @ ('+' | '-')) = self.peek(0) {
+                self.consume();
+                repr.push(sign);
+            }
+            self.consume_digits(&mut repr);
+        }
+        repr.parse().unwrap_or(0.0)
+    }
+
+    fn consume_digits(&mut self, repr: &mut String) {
+        while let Some(c) = self.peek(0).filter(char::is_ascii_digit) {
+            self.consume();
+            repr.push(c);
+        }
+    }
+
+    fn consume_numeric(&mut self) -> Token {
+        let number = self.consume_number();
+        if would_start_ident(self.peek(0), self.peek(1), self.peek(2)) {
+            Token::Dimension(number, self.consume_name())
+        } else if self.peek(0) == Some('%') {
+            self.consume();
+            Token::Percentage(number)
+        } else {
+            Token::Number(number)
+        }
+    }
+
+    fn consume_ident_like(&mut self) -> Token {
+        let name = self.consume_name();
+        if self.peek(0) != Some('(') {
+            return Token::Ident(name);
+        }
+        self.consume();
+        if !name.eq_ignore_ascii_case("url") {
+            return Token::Function(name);
+        }
+        while self.peek(0).is_some_and(is_whitespace) && self.peek(1).is_some_and(is_whitespace) {
+            self.consume();
+        }
+        let quoted = |c: Option<char>| c == Some('"') || c == Some('\'');
+        if quoted(self.peek(0)) || self.peek(0).is_some_and(is_whitespace) && quoted(self.peek(1)) {
+            // url("...") is a function taking a string.
+            Token::Function(name)
+        } else {
+            self.consume_url()
+        }
+    }
+
+    fn consume_url(&mut self) -> Token {
+        let mut url = String::new();
+        while self.peek(0).is_some_and(is_whitespace) {
+            self.consume();
+        }
+        loop {
+            match self.consume() {
+                Some(')') => return Token::Url(url),
+                None => {
+                    self.error("unexpected end of file in url");
+                    return Token::Url(url);
+                },
+                Some(c) if is_whitespace(c) => {
+                    while self.peek(0).is_some_and(is_whitespace) {
+                        self.consume();
+                    }
+                    match self.peek(0) {
+                        Some(')') => {
+                            self.consume();
+                            return Token::Url(url);
+                        },
+                        None => {
+                            self.error("unexpected end of file in url");
+                            return Token::Url(url);
+                        },
+                        _ => {
+                            self.error("whitespace in url");
+                            return self.consume_bad_url();
+                        },
+                    }
+                },
+                Some('"' | '\'' | '(') => {
+                    self.error("unexpected character in url");
+                    return self.consume_bad_url();
+                },
+                Some(c) if c.is_control() && c != '\t' => {
+                    self.error("non-printable character in url");
+                    return self.consume_bad_url();
+                },
+                Some('\\') => {
+                    if is_valid_escape(Some('\\'), self.peek(0)) {
+                        url.push(self.consume_escape());
+                    } else {
+                        self.error("invalid escape in url");
+                        return self.consume_bad_url();
+                    }
+                },
+                Some(c) => url.push(c),
+            }
+        }
+    }
+
+    // Skips the rest of an invalid url.
+    fn consume_bad_url(&mut self) -> Token {
+        loop {
+            match self.consume() {
+                Some(')') | None => return Token::BadUrl,
+                Some('\\') if is_valid_escape(Some('\\'), self.peek(0)) => {
+                    self.consume_escape();
+                },
+                _ => (),
+            }
+        }
+    }
+
+    fn consume_string(&mut self, quote: char) -> Token {
+        let mut string = String::new();
+        loop {
+            match self.peek(0) {
+                Some(c) if c == quote => {
+                    self.consume();
+                    return Token::String(string);
+                },
+                None => {
+                    self.error("unexpected end of file in string");
+                    return Token::String(string);
+                },
+                // The newline is left for the next token.
+                Some('\n') => {
+                    self.error("newline in string");
+                    return Token::BadString;
+                },
+                Some('\\') => {
+                    self.consume();
+                    match self.peek(0) {
+                        None => (),
+                        // An escaped newline continues the string on the next line.
+                        Some('\n') => { self.consume(); },
+                        Some(_) => string.push(self.consume_escape()),
+                    }
+                },
+                Some(c) => {
+                    self.consume();
+                    string.push(c);
+                },
+            }
+        }
+    }
+
+    fn consume_token(&mut self) -> Option<Token> {
+        let c = self.peek(0)?;
+        if is_whitespace(c) {
+            while self.peek(0).is_some_and(is_whitespace) {
+                self.consume();
+            }
+            return Some(Token::Whitespace);
+        }
+        if c.is_ascii_digit() || (matches!(c, '+' | '-' | '.') && would_start_number(Some(c), self.peek(1), self.peek(2))) {
+            return Some(self.consume_numeric());
+        }
+        if self.input[self.pos..].starts_with("-->") {
+            self.pos += 3;
+            return Some(Token::Cdc);
+        }
+        if would_start_ident(Some(c), self.peek(1), self.peek(2)) {
+            return Some(self.consume_ident_like());
+        }
+        self.consume();
+        Some(match c {
+            '"' | '\'' => self.consume_string(c),
+            '#' if self.peek(0).is_some_and(is_ident_char) || is_valid_escape(self.peek(0), self.peek(1)) => {
+                let is_id = would_start_ident(self.peek(0), self.peek(1), self.peek(2));
+                Token::Hash(self.consume_name(), is_id)
+            },
+            '<' if self.input[self.pos..].starts_with("!--") => {
+                self.pos += 3;
+                Token::Cdo
+            },
+            '@' if would_start_ident(self.peek(0), self.peek(1), self.peek(2)) => Token::AtKeyword(self.consume_name()),
+            '\\' => {
+                self.error("invalid escape");
+                Token::Delim('\\')
+            },
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '[' => Token::OpenSquare,
+            ']' => Token::CloseSquare,
+            '{' => Token::OpenCurly,
+            '}' => Token::CloseCurly,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            ';' => Token::Semicolon,
+            c => Token::Delim(c),
+        })
+    }
+}
+
+impl Iterator for Tokenizer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.consume_comments();
+        let start = self.pos;
+        let token = self.consume_token()?;
+        self.last_span = Span { start: self.location(start), end: self.location(self.pos) };
+        Some(token)
+    }
+}
+
+// Tokenizes a whole source, with the errors found.
+pub fn tokenize(source: &str) -> (Vec<Token>, Vec<ParseError>) {
+    let mut tokenizer = Tokenizer::new(source);
+    let tokens = tokenizer.by_ref().collect();
+    (tokens, tokenizer.take_errors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, Token};
+    use crate::css::parse_declaration_list;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source).0
+    }
+
+    fn error_messages(source: &str) -> Vec<String> {
+        tokenize(source).1.into_iter().map(|error| error.message).collect()
+    }
+
+    fn ident(name: &str) -> Token {
+        Token::Ident(name.to_string())
+    }
+
+    fn string(string: &str) -> Token {
+        Token::String(string.to_string())
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(tokens("\\41 b \\000041B \\31 23"), [ident("Ab"), Token::Whitespace, ident("AB"), Token::Whitespace, ident("123")]);
+        assert_eq!(tokens("a\\:b\\.c"), [ident("a:b.c")]);
+        // NUL, surrogates and code points past the last one are replaced.
+        assert_eq!(tokens("\\0 \\d800 \\110000"), [ident("\u{fffd}\u{fffd}\u{fffd}")]);
+        assert_eq!(tokens("\"\\22 \\\" \\\n\\a\""), [string("\"\" \n")]);
+        assert_eq!(tokens("#\\31 a .\\@b"), [Token::Hash("1a".to_string(), true), Token::Whitespace, Token::Delim('.'), ident("@b")]);
+        assert_eq!(tokens("#1a"), [Token::Hash("1a".to_string(), false)]);
+        // A backslash before a newline is not an escape.
+        assert_eq!(tokens("a\\\nb"), [ident("a"), Token::Delim('\\'), Token::Whitespace, ident("b")]);
+        assert_eq!(error_messages("a\\\nb"), ["invalid escape"]);
+        // At the end of the file, it is an escape of U+FFFD.
+        assert_eq!(tokens("a\\"), [ident("a\u{fffd}")]);
+        assert_eq!(error_messages("a\\"), ["unexpected end of file in escape"]);
+        assert_eq!(tokens("'a\\"), [string("a")]);
+        assert_eq!(error_messages("'a\\"), ["unexpected end of file in string"]);
+    }
+
+    #[test]
+    fn bad_strings() {
+        // The newline ends the string, and is tokenized on its own.
+        assert_eq!(tokens("'a\nb'"), [Token::BadString, Token::Whitespace, ident("b"), string("")]);
+        assert_eq!(error_messages("'a\nb"), ["newline in string"]);
+        // The declaration with the bad string is dropped, the next one is kept.
+        let (declarations, errors) = parse_declaration_list("content: \"a\n; color: red");
+        assert_eq!(declarations.iter().map(|declaration| declaration.name.as_str()).collect::<Vec<_>>(), ["color"]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(tokens("url(a.png)"), [Token::Url("a.png".to_string())]);
+        assert_eq!(tokens("URL(  a\\)b.png  )"), [Token::Url("a)b.png".to_string())]);
+        assert_eq!(tokens("url( 'a.png' )"), [Token::Function("url".to_string()), Token::Whitespace, string("a.png"),
+                                             Token::Whitespace, Token::CloseParen]);
+        assert_eq!(tokens("url(a.png"), [Token::Url("a.png".to_string())]);
+        assert_eq!(error_messages("url(a.png"), ["unexpected end of file in url"]);
+        // A bad url goes on to the next ')', skipping escaped ones.
+        assert_eq!(tokens("url(a b) c"), [Token::BadUrl, Token::Whitespace, ident("c")]);
+        assert_eq!(tokens("url(a\"b\\) c) d"), [Token::BadUrl, Token::Whitespace, ident("d")]);
+        assert_eq!(tokens("url(a(b) c"), [Token::BadUrl, Token::Whitespace, ident("c")]);
+        assert_eq!(tokens("url(a\x01) b"), [Token::BadUrl, Token::Whitespace, ident("b")]);
+        assert_eq!(tokens("url(a\\\n) b"), [Token::BadUrl, Token::Whitespace, ident("b")]);
+        assert_eq!(error_messages("url(a b)url(a\"b)url(a\x01)url(a\\\n)"),
+                   ["whitespace in url", "unexpected character in url", "non-printable character in url", "invalid escape in url"]);
+        let (declarations, _) = parse_declaration_list("background: url(a b); color: red");
+        assert_eq!(declarations.iter().map(|declaration| declaration.name.as_str()).collect::<Vec<_>>(), ["color"]);
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(tokens("a/* b */c /**/ /* * / */d"), [ident("a"), ident("c"), Token::Whitespace, Token::Whitespace, ident("d")]);
+        assert_eq!(tokens("a/*/b*/"), [ident("a")]);
+        assert_eq!(tokens("'/* a */' url(/*b*/)"), [string("/* a */"), Token::Whitespace, Token::Url("/*b*/".to_string())]);
+        assert_eq!(tokens("a /* b"), [ident("a"), Token::Whitespace]);
+        assert_eq!(error_messages("a /* b"), ["unterminated comment"]);
+        assert_eq!(tokens("<!-- a -->"), [Token::Cdo, Token::Whitespace, ident("a"), Token::Whitespace, Token::Cdc]);
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(tokens("12 +1.5 -.5 .5e1 1E+2 1e-1"), [
+            Token::Number(12.0), Token::Whitespace, Token::Number(1.5), Token::Whitespace, Token::Number(-0.5),
+            Token::Whitespace, Token::Number(5.0), Token::Whitespace, Token::Number(100.0), Token::Whitespace,
+            Token::Number(0.1),
+        ]);
+        // "e" is only an exponent if digits follow.
+        assert_eq!(tokens("1em 1e 1e+ 2e3px"), [
+            Token::Dimension(1.0, "em".to_string()), Token::Whitespace, Token::Dimension(1.0, "e".to_string()),
+            Token::Whitespace, Token::Dimension(1.0, "e".to_string()), Token::Delim('+'), Token::Whitespace,
+            Token::Dimension(2000.0, "px".to_string()),
+        ]);
+        assert_eq!(tokens("1.a 1. 50%"), [
+            Token::Number(1.0), Token::Delim('.'), ident("a"), Token::Whitespace, Token::Number(1.0), Token::Delim('.'),
+            Token::Whitespace, Token::Percentage(50.0),
+        ]);
+        assert_eq!(tokens("+a -a -1-2 1\\61"), [
+            Token::Delim('+'), ident("a"), Token::Whitespace, ident("-a"), Token::Whitespace, Token::Number(-1.0),
+            Token::Number(-2.0), Token::Whitespace, Token::Dimension(1.0, "a".to_string()),
+        ]);
+        assert_eq!(tokens("1--a --b"), [Token::Dimension(1.0, "--a".to_string()), Token::Whitespace, ident("--b")]);
+    }
+}
@@ -0,0 +1,950 @@
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::fmt;
+use std::ops::{Deref, Index, IndexMut};
+
+use crate::encoding::Encoding;
+use crate::source::Span;
+
+mod observer;
+mod traversal;
+
+pub use self::observer::{MutationCallback, MutationKind, MutationObserverInit, MutationRecord, ObserverId};
+use self::observer::Observers;
+pub use self::traversal::{Ancestors, Descendants, NodeFilter, Preceding, Siblings, TreeWalker};
+
+// Nodes are stored in the arena of their Tree, and refer to each other by
+// index. Nodes removed from the tree stay in the arena, so a NodeId stays valid
+// for the lifetime of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub node_type: NodeType,
+    // Where the node comes from in the parsed source, if it was parsed.
+    pub span: Option<Span>,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    previous_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+#[derive(Debug, Clone)]
+pub enum NodeType {
+    Document(DocumentData),
+    // Root of a tree of nodes which are not in a document, e.g. a parsed fragment.
+    DocumentFragment,
+    Doctype(DoctypeData),
+    Comment(String),
+    Text(String),
+    Element(ElementData),
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentData {
+    pub quirks_mode: QuirksMode,
+    // Encoding the document was decoded from.
+    pub encoding: Encoding,
+}
+
+// Rendering mode selected by the doctype of the document, see
+// https://quirks.spec.whatwg.org/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuirksMode {
+    NoQuirks,
+    LimitedQuirks,
+    Quirks,
+}
+
+#[derive(Debug, Clone)]
+pub struct DoctypeData {
+    pub name: String,
+    pub public_id: String,
+    pub system_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElementData {
+    pub namespace: Namespace,
+    // Lowercase for HTML elements, but case-sensitive in SVG, e.g. foreignObject.
+    pub tag_name: String,
+    attributes: AttrMap,
+}
+
+// Namespaces of elements and attributes, see https://infra.spec.whatwg.org/#namespaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+    Html,
+    Svg,
+    MathMl,
+    XLink,
+    Xml,
+    Xmlns,
+}
+
+impl Namespace {
+    pub fn url(self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+            Namespace::XLink => "http://www.w3.org/1999/xlink",
+            Namespace::Xml => "http://www.w3.org/XML/1998/namespace",
+            Namespace::Xmlns => "http://www.w3.org/2000/xmlns/",
+        }
+    }
+}
+
+impl Node {
+    fn new(node_type: NodeType) -> Node {
+        Node {
+            node_type,
+            span: None,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            previous_sibling: None,
+            next_sibling: None,
+        }
+    }
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+    pub fn first_child(&self) -> Option<NodeId> {
+        self.first_child
+    }
+    pub fn last_child(&self) -> Option<NodeId> {
+        self.last_child
+    }
+    pub fn previous_sibling(&self) -> Option<NodeId> {
+        self.previous_sibling
+    }
+    pub fn next_sibling(&self) -> Option<NodeId> {
+        self.next_sibling
+    }
+    pub fn element(&self) -> Option<&ElementData> {
+        match &self.node_type {
+            NodeType::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+    pub fn element_mut(&mut self) -> Option<&mut ElementData> {
+        match &mut self.node_type {
+            NodeType::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+    // Text of a Text or Comment node.
+    pub fn data(&self) -> Option<&str> {
+        match &self.node_type {
+            NodeType::Text(data) | NodeType::Comment(data) => Some(data),
+            _ => None,
+        }
+    }
+    // Changes made here are not seen by mutation observers, see Tree::set_data.
+    pub(crate) fn data_mut(&mut self) -> Option<&mut String> {
+        match &mut self.node_type {
+            NodeType::Text(data) | NodeType::Comment(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+// Errors of the mutation methods, named after the DOM exceptions, see
+// https://dom.spec.whatwg.org/#concept-node-tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DomError {
+    // The node cannot be inserted there, e.g. an ancestor of the parent, or a
+    // second element in a document.
+    HierarchyRequest,
+    // The child is not a child of the given parent.
+    NotFound,
+}
+
+impl fmt::Display for DomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DomError::HierarchyRequest => write!(f, "the node cannot be inserted at this position"),
+            DomError::NotFound => write!(f, "the node is not a child of this parent"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tree {
+    nodes: Vec<Node>,
+    observers: Observers,
+}
+
+impl Tree {
+    pub const ROOT: NodeId = NodeId(0);
+
+    // Tree made of a root node, usually a Document or a DocumentFragment.
+    pub fn new(root: NodeType) -> Tree {
+        Tree { nodes: vec![Node::new(root)], observers: Observers::default() }
+    }
+
+    pub fn new_document() -> Tree {
+        Tree::new(NodeType::Document(DocumentData {
+            quirks_mode: QuirksMode::NoQuirks,
+            encoding: Encoding::Utf8,
+        }))
+    }
+
+    pub fn root(&self) -> NodeRef<'_> {
+        self.get(Tree::ROOT)
+    }
+
+    // Quirks mode of the document, trees without a document are never in quirks mode.
+    pub fn quirks_mode(&self) -> QuirksMode {
+        match &self[Tree::ROOT].node_type {
+            NodeType::Document(document) => document.quirks_mode,
+            _ => QuirksMode::NoQuirks,
+        }
+    }
+
+    pub fn get(&self, id: NodeId) -> NodeRef<'_> {
+        NodeRef { tree: self, id }
+    }
+
+    // Creates a node outside of the tree, to be inserted in it.
+    pub fn create_node(&mut self, node_type: NodeType) -> NodeId {
+        self.nodes.push(Node::new(node_type));
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn create_element(&mut self, tag_name: &str) -> NodeId {
+        self.create_element_ns(Namespace::Html, tag_name)
+    }
+
+    pub fn create_element_ns(&mut self, namespace: Namespace, tag_name: &str) -> NodeId {
+        self.create_node(NodeType::Element(ElementData::new_ns(namespace, tag_name.to_string(), AttrMap::new())))
+    }
+
+    pub fn create_text(&mut self, data: &str) -> NodeId {
+        self.create_node(NodeType::Text(data.to_string()))
+    }
+
+    // Removes a node, with its descendants, from its parent.
+    pub fn detach(&mut self, id: NodeId) {
+        let node = &self[id];
+        if let Some(parent) = node.parent {
+            let (previous, next) = (node.previous_sibling, node.next_sibling);
+            self.queue_child_list(parent, Vec::new(), vec![id], previous, next);
+        }
+        self.unlink(id);
+    }
+
+    // Like detach, without notifying the observers.
+    fn unlink(&mut self, id: NodeId) {
+        let (parent, previous, next) = {
+            let node = &self[id];
+            (node.parent, node.previous_sibling, node.next_sibling)
+        };
+        let parent = match parent {
+            Some(parent) => parent,
+            None => return,
+        };
+        match previous {
+            Some(previous) => self[previous].next_sibling = next,
+            None => self[parent].first_child = next,
+        }
+        match next {
+            Some(next) => self[next].previous_sibling = previous,
+            None => self[parent].last_child = previous,
+        }
+        let node = &mut self[id];
+        node.parent = None;
+        node.previous_sibling = None;
+        node.next_sibling = None;
+    }
+
+    // Inserts a node in `parent` before `reference`, or last if there is no
+    // reference, without checking that the result is a valid tree. The node
+    // is first removed from where it was. Observers are not notified.
+    pub(crate) fn insert_unchecked(&mut self, parent: NodeId, id: NodeId, reference: Option<NodeId>) {
+        self.unlink(id);
+        let previous = match reference {
+            Some(reference) => self[reference].previous_sibling,
+            None => self[parent].last_child,
+        };
+        {
+            let node = &mut self[id];
+            node.parent = Some(parent);
+            node.previous_sibling = previous;
+            node.next_sibling = reference;
+        }
+        match previous {
+            Some(previous) => self[previous].next_sibling = Some(id),
+            None => self[parent].first_child = Some(id),
+        }
+        match reference {
+            Some(reference) => self[reference].previous_sibling = Some(id),
+            None => self[parent].last_child = Some(id),
+        }
+    }
+
+    // Inserts a node in `parent` before `reference`, or last if there is no
+    // reference. The node is first removed from where it was, and the children
+    // of a DocumentFragment are inserted instead of the fragment itself.
+    pub fn insert_before(&mut self, parent: NodeId, id: NodeId, reference: Option<NodeId>)
+                         -> Result<NodeId, DomError> {
+        self.check_insertion(parent, id, reference, None)?;
+        // Inserting a node before itself leaves it in place.
+        let reference = match reference {
+            Some(reference) if reference == id => self[id].next_sibling,
+            reference => reference,
+        };
+        self.insert_nodes(parent, id, reference, None);
+        Ok(id)
+    }
+
+    pub fn append_child(&mut self, parent: NodeId, id: NodeId) -> Result<NodeId, DomError> {
+        self.insert_before(parent, id, None)
+    }
+
+    // Returns the removed child.
+    pub fn remove_child(&mut self, parent: NodeId, child: NodeId) -> Result<NodeId, DomError> {
+        if self[child].parent != Some(parent) {
+            return Err(DomError::NotFound);
+        }
+        self.detach(child);
+        Ok(child)
+    }
+
+    // Puts a node in place of `child`, and returns the replaced child.
+    pub fn replace_child(&mut self, parent: NodeId, id: NodeId, child: NodeId) -> Result<NodeId, DomError> {
+        self.check_insertion(parent, id, Some(child), Some(child))?;
+        let mut reference = self[child].next_sibling;
+        if reference == Some(id) {
+            reference = self[id].next_sibling;
+        }
+        self.insert_nodes(parent, id, reference, Some(child));
+        Ok(child)
+    }
+
+    // Inserts the node, or the children of a DocumentFragment, in place of
+    // `replaced` if any, and notifies the observers.
+    fn insert_nodes(&mut self, parent: NodeId, id: NodeId, reference: Option<NodeId>, replaced: Option<NodeId>) {
+        let nodes: Vec<NodeId> = match self[id].node_type {
+            NodeType::DocumentFragment => {
+                let children: Vec<NodeId> = self.get(id).children().map(|child| child.id).collect();
+                if !children.is_empty() {
+                    self.queue_child_list(id, Vec::new(), children.clone(), None, None);
+                }
+                children
+            },
+            _ => {
+                self.detach(id);
+                vec![id]
+            },
+        };
+        if let Some(replaced) = replaced {
+            self.unlink(replaced);
+        }
+        let previous = match reference {
+            Some(reference) => self[reference].previous_sibling,
+            None => self[parent].last_child,
+        };
+        for &node in &nodes {
+            self.insert_unchecked(parent, node, reference);
+        }
+        if !nodes.is_empty() || replaced.is_some() {
+            self.queue_child_list(parent, nodes, replaced.into_iter().collect(), previous, reference);
+        }
+    }
+
+    fn is_inclusive_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut node = Some(id);
+        while let Some(current) = node {
+            if current == ancestor {
+                return true;
+            }
+            node = self[current].parent;
+        }
+        false
+    }
+
+    // Checks that inserting `id` before `reference` gives a valid tree, see
+    // https://dom.spec.whatwg.org/#concept-node-ensure-pre-insertion-validity
+    // When replacing, `replaced` is the child going away.
+    fn check_insertion(&self, parent: NodeId, id: NodeId, reference: Option<NodeId>,
+                       replaced: Option<NodeId>) -> Result<(), DomError> {
+        match self[parent].node_type {
+            NodeType::Document(_) | NodeType::DocumentFragment | NodeType::Element(_) => {},
+            _ => return Err(DomError::HierarchyRequest),
+        }
+        if self.is_inclusive_ancestor(id, parent) {
+            return Err(DomError::HierarchyRequest);
+        }
+        if reference.is_some_and(|reference| self[reference].parent != Some(parent)) {
+            return Err(DomError::NotFound);
+        }
+        let is_document = matches!(self[parent].node_type, NodeType::Document(_));
+        match &self[id].node_type {
+            NodeType::Document(_) => return Err(DomError::HierarchyRequest),
+            NodeType::Text(_) if is_document => return Err(DomError::HierarchyRequest),
+            NodeType::Doctype(_) if !is_document => return Err(DomError::HierarchyRequest),
+            _ => {},
+        }
+        if !is_document {
+            return Ok(());
+        }
+        // A document has at most one doctype, followed by at most one element.
+        let is_element = |id: &NodeId| matches!(self[*id].node_type, NodeType::Element(_));
+        let is_doctype = |id: &NodeId| matches!(self[*id].node_type, NodeType::Doctype(_));
+        // The replaced child does not count, as it is going away.
+        let children: Vec<NodeId> = self.get(parent).children()
+                                        .map(|child| child.id)
+                                        .filter(|&child| Some(child) != replaced)
+                                        .collect();
+        // Children before the insertion point, the rest comes after it.
+        let position = match reference {
+            Some(reference) if Some(reference) == replaced => {
+                self.get(reference).previous_sibling().map_or(0, |previous| {
+                    children.iter().position(|&child| child == previous.id).unwrap() + 1
+                })
+            },
+            Some(reference) => children.iter().position(|&child| child == reference).unwrap(),
+            None => children.len(),
+        };
+        let (before, after) = children.split_at(position);
+        let has_element = children.iter().any(is_element);
+        let inserts_element = match &self[id].node_type {
+            NodeType::DocumentFragment => {
+                let fragment_children: Vec<NodeId> = self.get(id).children().map(|child| child.id).collect();
+                let elements = fragment_children.iter().filter(|child| is_element(child)).count();
+                if elements > 1 || fragment_children.iter().any(|&child| matches!(self[child].node_type, NodeType::Text(_))) {
+                    return Err(DomError::HierarchyRequest);
+                }
+                elements == 1
+            },
+            NodeType::Element(_) => true,
+            NodeType::Doctype(_) => {
+                if children.iter().any(is_doctype) || before.iter().any(is_element) {
+                    return Err(DomError::HierarchyRequest);
+                }
+                false
+            },
+            _ => false,
+        };
+        if inserts_element && (has_element || after.iter().any(is_doctype)) {
+            return Err(DomError::HierarchyRequest);
+        }
+        Ok(())
+    }
+}
+
+impl Index<NodeId> for Tree {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+}
+
+impl IndexMut<NodeId> for Tree {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+}
+
+// Node of a tree, with access to the nodes around it.
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    tree: &'a Tree,
+    id: NodeId,
+}
+
+impl<'a> NodeRef<'a> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+    pub fn tree(&self) -> &'a Tree {
+        self.tree
+    }
+    pub fn node(&self) -> &'a Node {
+        &self.tree[self.id]
+    }
+    pub fn parent(&self) -> Option<NodeRef<'a>> {
+        self.node().parent.map(|id| self.tree.get(id))
+    }
+    pub fn first_child(&self) -> Option<NodeRef<'a>> {
+        self.node().first_child.map(|id| self.tree.get(id))
+    }
+    pub fn last_child(&self) -> Option<NodeRef<'a>> {
+        self.node().last_child.map(|id| self.tree.get(id))
+    }
+    pub fn previous_sibling(&self) -> Option<NodeRef<'a>> {
+        self.node().previous_sibling.map(|id| self.tree.get(id))
+    }
+    pub fn next_sibling(&self) -> Option<NodeRef<'a>> {
+        self.node().next_sibling.map(|id| self.tree.get(id))
+    }
+    pub fn children(&self) -> Children<'a> {
+        Children { tree: self.tree, next: self.node().first_child }
+    }
+    pub fn element(&self) -> Option<&'a ElementData> {
+        self.node().element()
+    }
+
+    pub fn get_element_by_id(&self, id: &str) -> Option<NodeRef<'a>> {
+        self.descendants().find(|node| node.element().is_some_and(|element| element.id().is_some_and(|other| other == id)))
+    }
+
+    // Descendant elements with the tag name, or all of them for "*". The name
+    // is case-insensitive for HTML elements only.
+    pub fn get_elements_by_tag_name(&self, tag_name: &str) -> Vec<NodeRef<'a>> {
+        let lowercase = tag_name.to_ascii_lowercase();
+        self.descendants()
+            .filter(|node| node.element().is_some_and(|element| {
+                tag_name == "*" || element.tag_name == if element.is_html() { lowercase.as_str() } else { tag_name }
+            }))
+            .collect()
+    }
+
+    // Descendant elements with all the space-separated classes.
+    pub fn get_elements_by_class_name(&self, class_names: &str) -> Vec<NodeRef<'a>> {
+        let class_names: Vec<&str> = class_names.split_whitespace().collect();
+        if class_names.is_empty() {
+            return Vec::new();
+        }
+        let quirks_mode = self.tree.quirks_mode();
+        self.descendants()
+            .filter(|node| node.element().is_some_and(|element| {
+                let classes = element.classes();
+                class_names.iter().all(|name| match quirks_mode {
+                    QuirksMode::Quirks => classes.iter().any(|class| class.eq_ignore_ascii_case(name)),
+                    _ => classes.contains(name),
+                })
+            }))
+            .collect()
+    }
+
+    // Data of a Text or Comment node, or the text of the descendant Text nodes.
+    // None for documents and doctypes.
+    pub fn text_content(&self) -> Option<String> {
+        match &self.node().node_type {
+            NodeType::Document(_) | NodeType::Doctype(_) => None,
+            NodeType::Text(data) | NodeType::Comment(data) => Some(data.clone()),
+            NodeType::Element(_) | NodeType::DocumentFragment => {
+                Some(self.descendants().filter_map(|node| match &node.node().node_type {
+                                           NodeType::Text(data) => Some(data.as_str()),
+                                           _ => None,
+                                       })
+                                       .collect())
+            },
+        }
+    }
+}
+
+impl Deref for NodeRef<'_> {
+    type Target = Node;
+
+    fn deref(&self) -> &Node {
+        self.node()
+    }
+}
+
+// Printing the whole tree for each node would be too much.
+impl fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NodeRef")
+         .field("id", &self.id)
+         .field("node_type", &self.node().node_type)
+         .field("span", &self.node().span)
+         .finish()
+    }
+}
+
+pub struct Children<'a> {
+    tree: &'a Tree,
+    next: Option<NodeId>,
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        let child = self.tree.get(self.next?);
+        self.next = child.node().next_sibling;
+        Some(child)
+    }
+}
+
+impl ElementData {
+    // HTML element.
+    pub fn new(tag_name: String, attributes: AttrMap) -> ElementData {
+        ElementData::new_ns(Namespace::Html, tag_name, attributes)
+    }
+    pub fn new_ns(namespace: Namespace, tag_name: String, attributes: AttrMap) -> ElementData {
+        ElementData { namespace, tag_name, attributes }
+    }
+    pub fn is_html(&self) -> bool {
+        self.namespace == Namespace::Html
+    }
+    pub fn attributes(&self) -> &AttrMap {
+        &self.attributes
+    }
+    // Changes made directly to the attributes, and with the methods below, are
+    // not seen by mutation observers: outside the parser, attributes are changed
+    // through the Tree, e.g. Tree::set_attribute.
+    pub(crate) fn attributes_mut(&mut self) -> &mut AttrMap {
+        &mut self.attributes
+    }
+    pub fn id(&self) -> Option<&String> {
+        self.attributes.get("id")
+    }
+    // Attribute names of HTML elements are case-insensitive, they are stored
+    // lowercased. Names are qualified names, e.g. "xlink:href".
+    fn attribute_name(&self, name: &str) -> String {
+        if self.is_html() { name.to_ascii_lowercase() } else { name.to_string() }
+    }
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(&self.attribute_name(name)).map(String::as_str)
+    }
+    // Attribute in a namespace, or without namespace for None, by local name.
+    pub fn get_attribute_ns(&self, namespace: Option<Namespace>, local_name: &str) -> Option<&str> {
+        self.attributes.iter_ns()
+                       .find(|(name, _, attr_namespace)| {
+                           *attr_namespace == namespace && name.rsplit(':').next() == Some(local_name)
+                       })
+                       .map(|(_, value, _)| value.as_str())
+    }
+    pub(crate) fn set_attribute(&mut self, name: &str, value: &str) {
+        let name = self.attribute_name(name);
+        self.attributes.insert(name, value.to_string());
+    }
+    pub(crate) fn remove_attribute(&mut self, name: &str) -> Option<String> {
+        let name = self.attribute_name(name);
+        self.attributes.remove(&name)
+    }
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attributes.contains_key(&self.attribute_name(name))
+    }
+    pub fn classes(&self) -> HashSet<&str> {
+        match self.attributes.get("class") {
+            Some(str_classes) => str_classes.split_whitespace().collect(),
+            None => HashSet::new(),
+        }
+    }
+    // Classes in the order of the class attribute, without duplicates.
+    pub fn class_list(&self) -> Vec<&str> {
+        let mut classes = Vec::new();
+        for class in self.attributes.get("class").into_iter().flat_map(|classes| classes.split_whitespace()) {
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        classes
+    }
+    pub fn has_class(&self, class: &str) -> bool {
+        self.class_list().contains(&class)
+    }
+    // Like DOMTokenList, the class attribute is rewritten with single spaces.
+    pub(crate) fn add_class(&mut self, class: &str) {
+        let mut classes = self.class_list();
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+        let value = classes.join(" ");
+        self.attributes.insert("class".to_string(), value);
+    }
+    pub(crate) fn remove_class(&mut self, class: &str) {
+        if !self.attributes.contains_key("class") {
+            return;
+        }
+        let mut classes = self.class_list();
+        classes.retain(|&other| other != class);
+        let value = classes.join(" ");
+        self.attributes.insert("class".to_string(), value);
+    }
+    // Adds the class if it is missing, removes it otherwise. Returns whether
+    // the element has the class afterwards.
+    pub(crate) fn toggle_class(&mut self, class: &str) -> bool {
+        if self.has_class(class) {
+            self.remove_class(class);
+            false
+        } else {
+            self.add_class(class);
+            true
+        }
+    }
+}
+
+// Attributes of an element, in source order so that serialization is stable.
+#[derive(Debug, Clone, Default)]
+pub struct AttrMap {
+    // Qualified name, value, and namespace of the few namespaced attributes
+    // of foreign elements, e.g. xlink:href.
+    entries: Vec<(String, String, Option<Namespace>)>,
+}
+
+impl AttrMap {
+    pub fn new() -> AttrMap {
+        AttrMap { entries: Vec::new() }
+    }
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.entries.iter().find(|(attr, _, _)| attr == name).map(|(_, value, _)| value)
+    }
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+    // Replaces the value of an existing attribute in place, or appends a new
+    // one without namespace.
+    pub fn insert(&mut self, name: String, value: String) -> Option<String> {
+        match self.entries.iter_mut().find(|(attr, _, _)| *attr == name) {
+            Some((_, old_value, _)) => Some(std::mem::replace(old_value, value)),
+            None => {
+                self.entries.push((name, value, None));
+                None
+            },
+        }
+    }
+    pub fn insert_ns(&mut self, namespace: Option<Namespace>, name: String, value: String) -> Option<String> {
+        let old_value = self.remove(&name);
+        self.entries.push((name, value, namespace));
+        old_value
+    }
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(attr, _, _)| attr == name)?;
+        Some(self.entries.remove(index).1)
+    }
+    pub fn namespace(&self, name: &str) -> Option<Namespace> {
+        self.entries.iter().find(|(attr, _, _)| attr == name).and_then(|(_, _, namespace)| *namespace)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.entries.iter().map(|(name, value, _)| (name, value))
+    }
+    pub fn iter_ns(&self) -> impl Iterator<Item = (&String, &String, Option<Namespace>)> {
+        self.entries.iter().map(|(name, value, namespace)| (name, value, *namespace))
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Two attribute lists are equal if they have the same attributes, in any order.
+impl PartialEq for AttrMap {
+    fn eq(&self, other: &AttrMap) -> bool {
+        self.len() == other.len() && self.iter().all(|(name, value)| other.get(name) == Some(value))
+    }
+}
+
+impl FromIterator<(String, String)> for AttrMap {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> AttrMap {
+        let mut attributes = AttrMap::new();
+        for (name, value) in iter {
+            if !attributes.contains_key(&name) {
+                attributes.entries.push((name, value, None));
+            }
+        }
+        attributes
+    }
+}
+
+impl fmt::Display for NodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn pretty_fmt(node: NodeRef, prefix: String, f: &mut fmt::Formatter) -> fmt::Result {
+            match &node.node_type {
+                NodeType::Document(DocumentData{quirks_mode, encoding}) => {
+                    writeln!(f, "{}#document ({:?}, {})", prefix, quirks_mode, encoding)?;
+                },
+                NodeType::DocumentFragment => writeln!(f, "{}#document-fragment", prefix)?,
+                NodeType::Doctype(DoctypeData{name, ..}) => {
+                    writeln!(f, "{}<!DOCTYPE {}>", prefix, name)?;
+                },
+                NodeType::Comment(text) => {
+                    writeln!(f, "{}<!--{}-->", prefix, text)?;
+                },
+                NodeType::Text(text) => {
+                    let to_write: String = text.chars().take(50).collect();
+                    writeln!(f, "{}{}", prefix, to_write)?;
+                },
+                NodeType::Element(ElementData{namespace, tag_name, attributes}) => {
+                    let mut str_attrs = "".to_string();
+                    for (attr, value) in attributes.iter() {
+                        if !str_attrs.is_empty() {str_attrs.push_str(", ")}
+                        str_attrs.push_str(&format!("{}={}", attr, value))
+                    }
+                    // Foreign elements are shown like "svg circle".
+                    let tag_name = match namespace {
+                        Namespace::Svg => format!("svg {}", tag_name),
+                        Namespace::MathMl => format!("math {}", tag_name),
+                        _ => tag_name.clone(),
+                    };
+                    match node.span {
+                        Some(span) => writeln!(f, "{}{} ({}) at {}", prefix, tag_name, str_attrs, span.start)?,
+                        None => writeln!(f, "{}{} ({})", prefix, tag_name, str_attrs)?,
+                    }
+                },
+            }
+            for child in node.children() {
+                let mut prefix = prefix.clone();
+                prefix.push_str("    ");
+                pretty_fmt(child, prefix, f)?;
+            }
+            Ok(())
+        }
+        pretty_fmt(*self, "".to_string(), f)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Tree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DoctypeData, DomError, NodeId, NodeType, Tree};
+
+    fn doctype() -> NodeType {
+        NodeType::Doctype(DoctypeData { name: "html".to_string(), public_id: String::new(), system_id: String::new() })
+    }
+
+    // Children of `parent`, after checking that the links between them go
+    // both ways.
+    fn children(tree: &Tree, parent: NodeId) -> Vec<NodeId> {
+        let forward: Vec<NodeId> = tree.get(parent).children().map(|child| child.id()).collect();
+        let mut backward: Vec<NodeId> = std::iter::successors(tree[parent].last_child(), |&child| tree[child].previous_sibling())
+                                            .collect();
+        backward.reverse();
+        assert_eq!(forward, backward, "siblings of {:?}", parent);
+        for &child in &forward {
+            assert_eq!(tree[child].parent(), Some(parent));
+        }
+        forward
+    }
+
+    fn assert_detached(tree: &Tree, id: NodeId) {
+        let node = &tree[id];
+        assert_eq!((node.parent(), node.previous_sibling(), node.next_sibling()), (None, None, None));
+    }
+
+    // Element with the children a, b and c, in a document fragment.
+    fn abc() -> (Tree, NodeId, [NodeId; 3]) {
+        let mut tree = Tree::new(NodeType::DocumentFragment);
+        let parent = tree.create_element("div");
+        tree.append_child(Tree::ROOT, parent).unwrap();
+        let nodes = [tree.create_element("a"), tree.create_text("b"), tree.create_element("c")];
+        for node in nodes {
+            tree.append_child(parent, node).unwrap();
+        }
+        (tree, parent, nodes)
+    }
+
+    #[test]
+    fn hierarchy_errors() {
+        let (mut tree, div, [a, b, _]) = abc();
+        let span = tree.create_element("span");
+        tree.append_child(a, span).unwrap();
+        // A node cannot go inside itself or its descendants.
+        assert_eq!(tree.append_child(span, div), Err(DomError::HierarchyRequest));
+        assert_eq!(tree.append_child(a, a), Err(DomError::HierarchyRequest));
+        assert_eq!(tree.insert_before(span, Tree::ROOT, None), Err(DomError::HierarchyRequest));
+        assert_eq!(tree.replace_child(a, div, span), Err(DomError::HierarchyRequest));
+        // Only documents, fragments and elements have children.
+        assert_eq!(tree.append_child(b, span), Err(DomError::HierarchyRequest));
+        // The reference and the removed child must be children of the parent.
+        assert_eq!(tree.insert_before(div, span, Some(span)), Err(DomError::NotFound));
+        assert_eq!(tree.remove_child(div, span), Err(DomError::NotFound));
+        assert_eq!(tree.replace_child(div, b, span), Err(DomError::NotFound));
+        // Failed insertions leave the tree as it was.
+        assert_eq!(children(&tree, a), [span]);
+        assert_eq!(children(&tree, div).len(), 3);
+    }
+
+    #[test]
+    fn document_hierarchy_errors() {
+        let mut tree = Tree::new_document();
+        let html = tree.create_element("html");
+        tree.append_child(Tree::ROOT, html).unwrap();
+        let body = tree.create_element("body");
+        let text = tree.create_text("a");
+        let node = tree.create_node(doctype());
+        // At most one element, no text, and the doctype before the element.
+        assert_eq!(tree.append_child(Tree::ROOT, body), Err(DomError::HierarchyRequest));
+        assert_eq!(tree.append_child(Tree::ROOT, text), Err(DomError::HierarchyRequest));
+        assert_eq!(tree.append_child(Tree::ROOT, node), Err(DomError::HierarchyRequest));
+        assert_eq!(tree.append_child(html, node), Err(DomError::HierarchyRequest));
+        tree.insert_before(Tree::ROOT, node, Some(html)).unwrap();
+        let other_doctype = tree.create_node(doctype());
+        assert_eq!(tree.insert_before(Tree::ROOT, other_doctype, Some(node)), Err(DomError::HierarchyRequest));
+        // The replaced element does not count.
+        assert_eq!(tree.replace_child(Tree::ROOT, body, html), Ok(html));
+        assert_eq!(children(&tree, Tree::ROOT), [node, body]);
+        // A fragment is checked for the children it would insert.
+        let mut fragment_tree = Tree::new_document();
+        let fragment = fragment_tree.create_node(NodeType::DocumentFragment);
+        let (first, second) = (fragment_tree.create_element("a"), fragment_tree.create_element("b"));
+        fragment_tree.append_child(fragment, first).unwrap();
+        fragment_tree.append_child(fragment, second).unwrap();
+        assert_eq!(fragment_tree.append_child(Tree::ROOT, fragment), Err(DomError::HierarchyRequest));
+        fragment_tree.remove_child(fragment, second).unwrap();
+        assert_eq!(fragment_tree.append_child(Tree::ROOT, fragment), Ok(fragment));
+        assert_eq!(children(&fragment_tree, Tree::ROOT), [first]);
+        assert_eq!(children(&fragment_tree, fragment), []);
+    }
+
+    #[test]
+    fn removing_keeps_the_siblings_linked() {
+        let (mut tree, div, [a, b, c]) = abc();
+        assert_eq!(tree.remove_child(div, b), Ok(b));
+        assert_detached(&tree, b);
+        assert_eq!(children(&tree, div), [a, c]);
+        tree.remove_child(div, a).unwrap();
+        assert_eq!(children(&tree, div), [c]);
+        tree.remove_child(div, c).unwrap();
+        assert_eq!(children(&tree, div), []);
+        assert_eq!((tree[div].first_child(), tree[div].last_child()), (None, None));
+        // A removed node keeps its own children.
+        tree.append_child(a, b).unwrap();
+        assert_eq!(children(&tree, a), [b]);
+    }
+
+    #[test]
+    fn replacing_keeps_the_siblings_linked() {
+        let (mut tree, div, [a, b, c]) = abc();
+        let d = tree.create_element("d");
+        assert_eq!(tree.replace_child(div, d, b), Ok(b));
+        assert_detached(&tree, b);
+        assert_eq!(children(&tree, div), [a, d, c]);
+        // By its next sibling, or by itself.
+        tree.replace_child(div, c, d).unwrap();
+        assert_eq!(children(&tree, div), [a, c]);
+        tree.replace_child(div, c, c).unwrap();
+        assert_eq!(children(&tree, div), [a, c]);
+        // By the children of a fragment.
+        let fragment = tree.create_node(NodeType::DocumentFragment);
+        let (x, y) = (tree.create_text("x"), tree.create_text("y"));
+        tree.append_child(fragment, x).unwrap();
+        tree.append_child(fragment, y).unwrap();
+        tree.replace_child(div, fragment, a).unwrap();
+        assert_eq!(children(&tree, div), [x, y, c]);
+        assert_eq!(children(&tree, fragment), []);
+        assert_detached(&tree, a);
+    }
+
+    #[test]
+    fn inserting_moves_the_node() {
+        let (mut tree, div, [a, b, c]) = abc();
+        tree.insert_before(div, c, Some(a)).unwrap();
+        assert_eq!(children(&tree, div), [c, a, b]);
+        tree.insert_before(div, a, Some(a)).unwrap();
+        assert_eq!(children(&tree, div), [c, a, b]);
+        tree.append_child(a, b).unwrap();
+        assert_eq!(children(&tree, div), [c, a]);
+        assert_eq!(children(&tree, a), [b]);
+        tree.append_child(Tree::ROOT, c).unwrap();
+        assert_eq!(children(&tree, div), [a]);
+        assert_eq!(children(&tree, Tree::ROOT), [div, c]);
+    }
+}
@@ -0,0 +1,386 @@
+use std::fmt;
+
+use crate::dom::{ElementData, NodeId, Tree};
+
+// Mutation observers, see https://dom.spec.whatwg.org/#mutation-observers
+// Changes made through the Tree are recorded for the observers of the changed
+// node and of its ancestors, and handed to their callbacks in batches when
+// `notify_observers` is called, e.g. once per frame.
+
+// What to observe on a node. Asking for old values or filtering attributes
+// implies observing them.
+#[derive(Debug, Clone, Default)]
+pub struct MutationObserverInit {
+    pub child_list: bool,
+    pub attributes: bool,
+    pub character_data: bool,
+    // Also observe the descendants of the node.
+    pub subtree: bool,
+    pub attribute_old_value: bool,
+    pub character_data_old_value: bool,
+    // Only observe these attributes.
+    pub attribute_filter: Option<Vec<String>>,
+}
+
+impl MutationObserverInit {
+    fn observes_attribute(&self, name: &str) -> bool {
+        (self.attributes || self.attribute_old_value || self.attribute_filter.is_some())
+            && self.attribute_filter.as_ref().is_none_or(|filter| filter.iter().any(|attr| attr == name))
+    }
+
+    fn observes_character_data(&self) -> bool {
+        self.character_data || self.character_data_old_value
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MutationKind {
+    ChildList,
+    Attributes,
+    CharacterData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutationRecord {
+    pub kind: MutationKind,
+    // Parent of the added and removed nodes, element of the attribute, or
+    // changed Text or Comment node.
+    pub target: NodeId,
+    pub added_nodes: Vec<NodeId>,
+    pub removed_nodes: Vec<NodeId>,
+    // Siblings around the added and removed nodes.
+    pub previous_sibling: Option<NodeId>,
+    pub next_sibling: Option<NodeId>,
+    pub attribute_name: Option<String>,
+    // Value before the change, if the observer asked for it. None for an
+    // attribute which did not exist.
+    pub old_value: Option<String>,
+}
+
+impl MutationRecord {
+    fn new(kind: MutationKind, target: NodeId) -> MutationRecord {
+        MutationRecord {
+            kind,
+            target,
+            added_nodes: Vec::new(),
+            removed_nodes: Vec::new(),
+            previous_sibling: None,
+            next_sibling: None,
+            attribute_name: None,
+            old_value: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+pub type MutationCallback = Box<dyn FnMut(&[MutationRecord])>;
+
+struct Observer {
+    id: ObserverId,
+    callback: MutationCallback,
+    // Observed nodes, with what is observed on each.
+    targets: Vec<(NodeId, MutationObserverInit)>,
+    // Records not delivered yet.
+    records: Vec<MutationRecord>,
+}
+
+// Observers of a tree. A cloned tree has no observers, like a cloned node.
+#[derive(Default)]
+pub(crate) struct Observers {
+    observers: Vec<Observer>,
+    next_id: usize,
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Observers {
+        Observers::default()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} observers", self.observers.len())
+    }
+}
+
+impl Observers {
+    fn get_mut(&mut self, id: ObserverId) -> Option<&mut Observer> {
+        self.observers.iter_mut().find(|observer| observer.id == id)
+    }
+
+    // Gives the record to the observers of its target, or of one of the
+    // `ancestors` of the target for subtree observers, see
+    // https://dom.spec.whatwg.org/#queue-a-mutation-record
+    fn queue(&mut self, record: MutationRecord, ancestors: &[NodeId]) {
+        for observer in &mut self.observers {
+            let mut interested = false;
+            let mut old_value = false;
+            for (node, options) in &observer.targets {
+                if *node != record.target && !(options.subtree && ancestors.contains(node)) {
+                    continue;
+                }
+                match record.kind {
+                    MutationKind::ChildList if options.child_list => {},
+                    MutationKind::Attributes if options.observes_attribute(record.attribute_name.as_deref().unwrap_or("")) => {
+                        old_value |= options.attribute_old_value;
+                    },
+                    MutationKind::CharacterData if options.observes_character_data() => {
+                        old_value |= options.character_data_old_value;
+                    },
+                    _ => continue,
+                }
+                interested = true;
+            }
+            if interested {
+                let mut record = record.clone();
+                if !old_value {
+                    record.old_value = None;
+                }
+                observer.records.push(record);
+            }
+        }
+    }
+}
+
+impl Tree {
+    // Registers an observer, which does not observe anything until `observe`
+    // is called.
+    pub fn add_observer(&mut self, callback: MutationCallback) -> ObserverId {
+        let id = ObserverId(self.observers.next_id);
+        self.observers.next_id += 1;
+        self.observers.observers.push(Observer { id, callback, targets: Vec::new(), records: Vec::new() });
+        id
+    }
+
+    // Observes a node. Observing it again replaces the options.
+    pub fn observe(&mut self, observer: ObserverId, target: NodeId, options: MutationObserverInit) {
+        if let Some(observer) = self.observers.get_mut(observer) {
+            observer.targets.retain(|(node, _)| *node != target);
+            observer.targets.push((target, options));
+        }
+    }
+
+    // Stops observing all nodes, and drops the records not delivered yet.
+    pub fn disconnect(&mut self, observer: ObserverId) {
+        if let Some(observer) = self.observers.get_mut(observer) {
+            observer.targets.clear();
+            observer.records.clear();
+        }
+    }
+
+    pub fn remove_observer(&mut self, observer: ObserverId) {
+        self.observers.observers.retain(|other| other.id != observer);
+    }
+
+    // Records not delivered yet, which will not be given to the callback.
+    pub fn take_records(&mut self, observer: ObserverId) -> Vec<MutationRecord> {
+        self.observers.get_mut(observer).map_or(Vec::new(), |observer| std::mem::take(&mut observer.records))
+    }
+
+    // Calls the callback of each observer having records, in the order the
+    // observers were added.
+    pub fn notify_observers(&mut self) {
+        for observer in &mut self.observers.observers {
+            let records = std::mem::take(&mut observer.records);
+            if !records.is_empty() {
+                (observer.callback)(&records);
+            }
+        }
+    }
+
+    fn queue_record(&mut self, record: MutationRecord) {
+        if self.observers.observers.is_empty() {
+            return;
+        }
+        let ancestors: Vec<NodeId> = std::iter::successors(self[record.target].parent, |&node| self[node].parent).collect();
+        self.observers.queue(record, &ancestors);
+    }
+
+    pub(crate) fn queue_child_list(&mut self, target: NodeId, added_nodes: Vec<NodeId>, removed_nodes: Vec<NodeId>,
+                                   previous_sibling: Option<NodeId>, next_sibling: Option<NodeId>) {
+        self.queue_record(MutationRecord {
+            added_nodes,
+            removed_nodes,
+            previous_sibling,
+            next_sibling,
+            ..MutationRecord::new(MutationKind::ChildList, target)
+        });
+    }
+
+    // Applies a change to an attribute of an element, and notifies the observers
+    // if the attribute existed or exists afterwards.
+    fn change_attribute<R>(&mut self, id: NodeId, name: &str, change: impl FnOnce(&mut ElementData) -> R) -> Option<R> {
+        let element = self[id].element_mut()?;
+        // The name as stored, lowercased on HTML elements only, e.g. viewBox on SVG.
+        let attribute_name = element.attribute_name(name);
+        let old_value = element.get_attribute(name).map(str::to_string);
+        let result = change(element);
+        if old_value.is_some() || element.has_attribute(name) {
+            self.queue_record(MutationRecord {
+                attribute_name: Some(attribute_name),
+                old_value,
+                ..MutationRecord::new(MutationKind::Attributes, id)
+            });
+        }
+        Some(result)
+    }
+
+    // The attribute methods of ElementData, notifying the observers. They do
+    // nothing on nodes which are not elements.
+    pub fn set_attribute(&mut self, id: NodeId, name: &str, value: &str) {
+        self.change_attribute(id, name, |element| element.set_attribute(name, value));
+    }
+
+    pub fn remove_attribute(&mut self, id: NodeId, name: &str) -> Option<String> {
+        self.change_attribute(id, name, |element| element.remove_attribute(name)).flatten()
+    }
+
+    pub fn add_class(&mut self, id: NodeId, class: &str) {
+        self.change_attribute(id, "class", |element| element.add_class(class));
+    }
+
+    pub fn remove_class(&mut self, id: NodeId, class: &str) {
+        self.change_attribute(id, "class", |element| element.remove_class(class));
+    }
+
+    pub fn toggle_class(&mut self, id: NodeId, class: &str) -> bool {
+        self.change_attribute(id, "class", |element| element.toggle_class(class)).unwrap_or(false)
+    }
+
+    // Replaces the text of a Text or Comment node.
+    pub fn set_data(&mut self, id: NodeId, data: &str) {
+        let old_value = match self[id].data_mut() {
+            Some(old_data) => std::mem::replace(old_data, data.to_string()),
+            None => return,
+        };
+        self.queue_record(MutationRecord {
+            old_value: Some(old_value),
+            ..MutationRecord::new(MutationKind::CharacterData, id)
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::{MutationKind, MutationObserverInit, MutationRecord, ObserverId};
+    use crate::dom::{NodeId, NodeType, Tree};
+
+    // Fragment with a <div class=a> holding a text node.
+    fn div_with_text() -> (Tree, NodeId, NodeId) {
+        let mut tree = Tree::new(NodeType::DocumentFragment);
+        let div = tree.create_element("div");
+        let text = tree.create_text("a");
+        tree.append_child(Tree::ROOT, div).unwrap();
+        tree.append_child(div, text).unwrap();
+        tree.set_attribute(div, "class", "a");
+        (tree, div, text)
+    }
+
+    fn observe(tree: &mut Tree, target: NodeId, options: MutationObserverInit) -> ObserverId {
+        let observer = tree.add_observer(Box::new(|_| {}));
+        tree.observe(observer, target, options);
+        observer
+    }
+
+    fn attribute(target: NodeId, name: &str, old_value: Option<&str>) -> MutationRecord {
+        MutationRecord {
+            attribute_name: Some(name.to_string()),
+            old_value: old_value.map(str::to_string),
+            ..MutationRecord::new(MutationKind::Attributes, target)
+        }
+    }
+
+    #[test]
+    fn attribute_old_values() {
+        let (mut tree, div, _) = div_with_text();
+        let observer = observe(&mut tree, div, MutationObserverInit { attribute_old_value: true, ..Default::default() });
+        tree.set_attribute(div, "ID", "x");
+        tree.set_attribute(div, "id", "y");
+        assert!(tree.toggle_class(div, "b"));
+        tree.remove_class(div, "a");
+        assert_eq!(tree.remove_attribute(div, "id"), Some("y".to_string()));
+        // Nothing changes, and there is no record, for a missing attribute.
+        assert_eq!(tree.remove_attribute(div, "title"), None);
+        assert_eq!(tree.take_records(observer), [
+            attribute(div, "id", None),
+            attribute(div, "id", Some("x")),
+            attribute(div, "class", Some("a")),
+            attribute(div, "class", Some("a b")),
+            attribute(div, "id", Some("y")),
+        ]);
+    }
+
+    #[test]
+    fn old_values_only_when_asked_for() {
+        let (mut tree, div, text) = div_with_text();
+        let options = MutationObserverInit { attributes: true, character_data: true, subtree: true, ..Default::default() };
+        let observer = observe(&mut tree, div, options);
+        let with_old_values = observe(&mut tree, text, MutationObserverInit { character_data_old_value: true, ..Default::default() });
+        tree.toggle_class(div, "a");
+        tree.set_data(text, "b");
+        assert_eq!(tree.take_records(observer), [
+            attribute(div, "class", None),
+            MutationRecord::new(MutationKind::CharacterData, text),
+        ]);
+        assert_eq!(tree.take_records(with_old_values), [MutationRecord {
+            old_value: Some("a".to_string()),
+            ..MutationRecord::new(MutationKind::CharacterData, text)
+        }]);
+        assert_eq!(tree.get(text).text_content().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn attribute_filter_and_subtree() {
+        let (mut tree, div, _) = div_with_text();
+        let span = tree.create_element("span");
+        tree.append_child(div, span).unwrap();
+        let filter = Some(vec!["title".to_string()]);
+        let filtered = observe(&mut tree, div, MutationObserverInit { attribute_filter: filter, subtree: true, ..Default::default() });
+        let not_subtree = observe(&mut tree, div, MutationObserverInit { attributes: true, ..Default::default() });
+        tree.set_attribute(span, "id", "a");
+        tree.set_attribute(span, "title", "b");
+        assert_eq!(tree.take_records(filtered), [attribute(span, "title", None)]);
+        assert_eq!(tree.take_records(not_subtree), []);
+    }
+
+    #[test]
+    fn child_list_records() {
+        let (mut tree, div, text) = div_with_text();
+        let observer = observe(&mut tree, div, MutationObserverInit { child_list: true, ..Default::default() });
+        let span = tree.create_element("span");
+        tree.insert_before(div, span, Some(text)).unwrap();
+        tree.remove_child(div, text).unwrap();
+        assert_eq!(tree.take_records(observer), [
+            MutationRecord { added_nodes: vec![span], next_sibling: Some(text), ..MutationRecord::new(MutationKind::ChildList, div) },
+            MutationRecord { removed_nodes: vec![text], previous_sibling: Some(span), ..MutationRecord::new(MutationKind::ChildList, div) },
+        ]);
+    }
+
+    #[test]
+    fn records_are_delivered_in_batches() {
+        let (mut tree, div, text) = div_with_text();
+        let delivered = Rc::new(RefCell::new(Vec::new()));
+        let sink = delivered.clone();
+        let observer = tree.add_observer(Box::new(move |records| sink.borrow_mut().push(records.len())));
+        tree.observe(observer, div, MutationObserverInit { attributes: true, character_data: true, subtree: true, ..Default::default() });
+        tree.set_attribute(div, "id", "a");
+        tree.set_data(text, "b");
+        tree.notify_observers();
+        tree.notify_observers();
+        tree.set_attribute(div, "id", "c");
+        tree.notify_observers();
+        assert_eq!(*delivered.borrow(), [2, 1]);
+        // Once disconnected, nothing is recorded.
+        tree.set_attribute(div, "id", "d");
+        tree.disconnect(observer);
+        tree.set_attribute(div, "id", "e");
+        tree.notify_observers();
+        assert_eq!(*delivered.borrow(), [2, 1]);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::dom::{NodeId, NodeRef};
+
+// Traversals of the tree around a node, see https://dom.spec.whatwg.org/#traversal
+
+impl<'a> NodeRef<'a> {
+    // Descendants of the node, in document order.
+    pub fn descendants(&self) -> Descendants<'a> {
+        Descendants { root: Some(self.id), next: self.first_child() }
+    }
+
+    // Parent of the node, then its parent, up to the root.
+    pub fn ancestors(&self) -> Ancestors<'a> {
+        Ancestors { next: self.parent() }
+    }
+
+    // Siblings after the node, from the closest one.
+    pub fn following_siblings(&self) -> Siblings<'a> {
+        Siblings { next: self.next_sibling(), forward: true }
+    }
+
+    // Siblings before the node, from the closest one.
+    pub fn preceding_siblings(&self) -> Siblings<'a> {
+        Siblings { next: self.previous_sibling(), forward: false }
+    }
+
+    // Nodes after this one in document order, starting with its descendants.
+    pub fn following(&self) -> Descendants<'a> {
+        Descendants { root: None, next: next_in_document_order(*self, None) }
+    }
+
+    // Nodes before this one in reverse document order, including its ancestors.
+    pub fn preceding(&self) -> Preceding<'a> {
+        Preceding { next: previous_in_document_order(*self) }
+    }
+
+    // Walks the descendants of the node accepted by the filter, like a
+    // TreeWalker. A node rejected by the filter is skipped with its descendants.
+    pub fn walk<F: FnMut(NodeRef<'a>) -> NodeFilter>(&self, filter: F) -> TreeWalker<'a, F> {
+        TreeWalker { root: *self, current: *self, filter }
+    }
+}
+
+// Node following `node` in document order, staying under `root` if given.
+fn next_in_document_order(node: NodeRef, root: Option<NodeId>) -> Option<NodeRef> {
+    if let Some(child) = node.first_child() {
+        return Some(child);
+    }
+    let mut current = node;
+    loop {
+        if Some(current.id) == root {
+            return None;
+        }
+        if let Some(sibling) = current.next_sibling() {
+            return Some(sibling);
+        }
+        current = current.parent()?;
+    }
+}
+
+fn previous_in_document_order(node: NodeRef) -> Option<NodeRef> {
+    match node.previous_sibling() {
+        Some(mut previous) => {
+            while let Some(child) = previous.last_child() {
+                previous = child;
+            }
+            Some(previous)
+        },
+        None => node.parent(),
+    }
+}
+
+// Preorder traversal of the nodes under `root`, or up to the end of the tree.
+pub struct Descendants<'a> {
+    root: Option<NodeId>,
+    next: Option<NodeRef<'a>>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        let node = self.next?;
+        self.next = next_in_document_order(node, self.root);
+        Some(node)
+    }
+}
+
+pub struct Ancestors<'a> {
+    next: Option<NodeRef<'a>>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        let node = self.next?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+pub struct Siblings<'a> {
+    next: Option<NodeRef<'a>>,
+    forward: bool,
+}
+
+impl<'a> Iterator for Siblings<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        let node = self.next?;
+        self.next = if self.forward { node.next_sibling() } else { node.previous_sibling() };
+        Some(node)
+    }
+}
+
+pub struct Preceding<'a> {
+    next: Option<NodeRef<'a>>,
+}
+
+impl<'a> Iterator for Preceding<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        let node = self.next?;
+        self.next = previous_in_document_order(node);
+        Some(node)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeFilter {
+    Accept,
+    // Skips the node but not its descendants.
+    Skip,
+    // Skips the node and its descendants.
+    Reject,
+}
+
+// Moves between the accepted nodes under `root`. Iterating goes forward in
+// document order from the current node.
+pub struct TreeWalker<'a, F> {
+    root: NodeRef<'a>,
+    current: NodeRef<'a>,
+    filter: F,
+}
+
+impl<'a, F: FnMut(NodeRef<'a>) -> NodeFilter> TreeWalker<'a, F> {
+    pub fn current(&self) -> NodeRef<'a> {
+        self.current
+    }
+
+    fn accept(&mut self, node: NodeRef<'a>) -> NodeFilter {
+        let result = (self.filter)(node);
+        if result == NodeFilter::Accept {
+            self.current = node;
+        }
+        result
+    }
+
+    // Closest accepted ancestor of the current node.
+    pub fn parent_node(&mut self) -> Option<NodeRef<'a>> {
+        let mut node = self.current;
+        while node.id != self.root.id {
+            node = node.parent()?;
+            if self.accept(node) == NodeFilter::Accept {
+                return Some(node);
+            }
+        }
+        None
+    }
+
+    pub fn next_node(&mut self) -> Option<NodeRef<'a>> {
+        let mut node = self.current;
+        let mut result = NodeFilter::Accept;
+        loop {
+            while result != NodeFilter::Reject {
+                match node.first_child() {
+                    Some(child) => node = child,
+                    None => break,
+                }
+                result = self.accept(node);
+                if result == NodeFilter::Accept {
+                    return Some(node);
+                }
+            }
+            // Rejected nodes and leaves continue with the next sibling of
+            // themselves or of an ancestor.
+            loop {
+                if node.id == self.root.id {
+                    return None;
+                }
+                if let Some(sibling) = node.next_sibling() {
+                    node = sibling;
+                    break;
+                }
+                node = node.parent()?;
+            }
+            result = self.accept(node);
+            if result == NodeFilter::Accept {
+                return Some(node);
+            }
+        }
+    }
+
+    pub fn previous_node(&mut self) -> Option<NodeRef<'a>> {
+        let mut node = self.current;
+        while node.id != self.root.id {
+            let mut sibling = node.previous_sibling();
+            while let Some(previous) = sibling {
+                node = previous;
+                let mut result = (self.filter)(node);
+                while result != NodeFilter::Reject {
+                    match node.last_child() {
+                        Some(child) => node = child,
+                        None => break,
+                    }
+                    result = (self.filter)(node);
+                }
+                if result == NodeFilter::Accept {
+                    self.current = node;
+                    return Some(node);
+                }
+                sibling = node.previous_sibling();
+            }
+            if node.id == self.root.id {
+                return None;
+            }
+            node = node.parent()?;
+            if self.accept(node) == NodeFilter::Accept {
+                return Some(node);
+            }
+        }
+        None
+    }
+}
+
+impl<'a, F: FnMut(NodeRef<'a>) -> NodeFilter> Iterator for TreeWalker<'a, F> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        self.next_node()
+    }
+}
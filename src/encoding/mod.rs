@@ -0,0 +1,336 @@
+use std::fmt;
+
+mod tables;
+
+// Character encodings of the WHATWG Encoding standard which documents can be
+// decoded from: https://encoding.spec.whatwg.org/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+    Windows1254,
+    Iso8859_2,
+    Iso8859_3,
+    Iso8859_4,
+    Iso8859_5,
+    Iso8859_6,
+    Iso8859_7,
+    Iso8859_8,
+    // Same characters as ISO-8859-8, in logical instead of visual order.
+    Iso8859_8I,
+    Iso8859_10,
+    Iso8859_13,
+    Iso8859_14,
+    Iso8859_15,
+    Iso8859_16,
+}
+
+const LABELS: &[(&str, Encoding)] = &[
+    ("unicode-1-1-utf-8", Encoding::Utf8),
+    ("unicode11utf8", Encoding::Utf8),
+    ("unicode20utf8", Encoding::Utf8),
+    ("utf-8", Encoding::Utf8),
+    ("utf8", Encoding::Utf8),
+    ("x-unicode20utf8", Encoding::Utf8),
+    ("unicodefffe", Encoding::Utf16Be),
+    ("utf-16be", Encoding::Utf16Be),
+    ("csunicode", Encoding::Utf16Le),
+    ("iso-10646-ucs-2", Encoding::Utf16Le),
+    ("ucs-2", Encoding::Utf16Le),
+    ("unicode", Encoding::Utf16Le),
+    ("unicodefeff", Encoding::Utf16Le),
+    ("utf-16", Encoding::Utf16Le),
+    ("utf-16le", Encoding::Utf16Le),
+    ("ansi_x3.4-1968", Encoding::Windows1252),
+    ("ascii", Encoding::Windows1252),
+    ("cp1252", Encoding::Windows1252),
+    ("cp819", Encoding::Windows1252),
+    ("csisolatin1", Encoding::Windows1252),
+    ("ibm819", Encoding::Windows1252),
+    ("iso-8859-1", Encoding::Windows1252),
+    ("iso-ir-100", Encoding::Windows1252),
+    ("iso8859-1", Encoding::Windows1252),
+    ("iso88591", Encoding::Windows1252),
+    ("iso_8859-1", Encoding::Windows1252),
+    ("iso_8859-1:1987", Encoding::Windows1252),
+    ("l1", Encoding::Windows1252),
+    ("latin1", Encoding::Windows1252),
+    ("us-ascii", Encoding::Windows1252),
+    ("windows-1252", Encoding::Windows1252),
+    ("x-cp1252", Encoding::Windows1252),
+    ("cp1254", Encoding::Windows1254),
+    ("csisolatin5", Encoding::Windows1254),
+    ("iso-8859-9", Encoding::Windows1254),
+    ("iso-ir-148", Encoding::Windows1254),
+    ("iso8859-9", Encoding::Windows1254),
+    ("iso88599", Encoding::Windows1254),
+    ("iso_8859-9", Encoding::Windows1254),
+    ("iso_8859-9:1989", Encoding::Windows1254),
+    ("l5", Encoding::Windows1254),
+    ("latin5", Encoding::Windows1254),
+    ("windows-1254", Encoding::Windows1254),
+    ("x-cp1254", Encoding::Windows1254),
+    ("csisolatin2", Encoding::Iso8859_2),
+    ("iso-8859-2", Encoding::Iso8859_2),
+    ("iso-ir-101", Encoding::Iso8859_2),
+    ("iso8859-2", Encoding::Iso8859_2),
+    ("iso88592", Encoding::Iso8859_2),
+    ("iso_8859-2", Encoding::Iso8859_2),
+    ("iso_8859-2:1987", Encoding::Iso8859_2),
+    ("l2", Encoding::Iso8859_2),
+    ("latin2", Encoding::Iso8859_2),
+    ("csisolatin3", Encoding::Iso8859_3),
+    ("iso-8859-3", Encoding::Iso8859_3),
+    ("iso-ir-109", Encoding::Iso8859_3),
+    ("iso8859-3", Encoding::Iso8859_3),
+    ("iso88593", Encoding::Iso8859_3),
+    ("iso_8859-3", Encoding::Iso8859_3),
+    ("iso_8859-3:1988", Encoding::Iso8859_3),
+    ("l3", Encoding::Iso8859_3),
+    ("latin3", Encoding::Iso8859_3),
+    ("csisolatin4", Encoding::Iso8859_4),
+    ("iso-8859-4", Encoding::Iso8859_4),
+    ("iso-ir-110", Encoding::Iso8859_4),
+    ("iso8859-4", Encoding::Iso8859_4),
+    ("iso88594", Encoding::Iso8859_4),
+    ("iso_8859-4", Encoding::Iso8859_4),
+    ("iso_8859-4:1988", Encoding::Iso8859_4),
+    ("l4", Encoding::Iso8859_4),
+    ("latin4", Encoding::Iso8859_4),
+    ("csisolatincyrillic", Encoding::Iso8859_5),
+    ("cyrillic", Encoding::Iso8859_5),
+    ("iso-8859-5", Encoding::Iso8859_5),
+    ("iso-ir-144", Encoding::Iso8859_5),
+    ("iso8859-5", Encoding::Iso8859_5),
+    ("iso88595", Encoding::Iso8859_5),
+    ("iso_8859-5", Encoding::Iso8859_5),
+    ("iso_8859-5:1988", Encoding::Iso8859_5),
+    ("arabic", Encoding::Iso8859_6),
+    ("asmo-708", Encoding::Iso8859_6),
+    ("csiso88596e", Encoding::Iso8859_6),
+    ("csiso88596i", Encoding::Iso8859_6),
+    ("csisolatinarabic", Encoding::Iso8859_6),
+    ("ecma-114", Encoding::Iso8859_6),
+    ("iso-8859-6", Encoding::Iso8859_6),
+    ("iso-8859-6-e", Encoding::Iso8859_6),
+    ("iso-8859-6-i", Encoding::Iso8859_6),
+    ("iso-ir-127", Encoding::Iso8859_6),
+    ("iso8859-6", Encoding::Iso8859_6),
+    ("iso88596", Encoding::Iso8859_6),
+    ("iso_8859-6", Encoding::Iso8859_6),
+    ("iso_8859-6:1987", Encoding::Iso8859_6),
+    ("csisolatingreek", Encoding::Iso8859_7),
+    ("ecma-118", Encoding::Iso8859_7),
+    ("elot_928", Encoding::Iso8859_7),
+    ("greek", Encoding::Iso8859_7),
+    ("greek8", Encoding::Iso8859_7),
+    ("iso-8859-7", Encoding::Iso8859_7),
+    ("iso-ir-126", Encoding::Iso8859_7),
+    ("iso8859-7", Encoding::Iso8859_7),
+    ("iso88597", Encoding::Iso8859_7),
+    ("iso_8859-7", Encoding::Iso8859_7),
+    ("iso_8859-7:1987", Encoding::Iso8859_7),
+    ("sun_eu_greek", Encoding::Iso8859_7),
+    ("csiso88598e", Encoding::Iso8859_8),
+    ("csisolatinhebrew", Encoding::Iso8859_8),
+    ("hebrew", Encoding::Iso8859_8),
+    ("iso-8859-8", Encoding::Iso8859_8),
+    ("iso-8859-8-e", Encoding::Iso8859_8),
+    ("iso-ir-138", Encoding::Iso8859_8),
+    ("iso8859-8", Encoding::Iso8859_8),
+    ("iso88598", Encoding::Iso8859_8),
+    ("iso_8859-8", Encoding::Iso8859_8),
+    ("iso_8859-8:1988", Encoding::Iso8859_8),
+    ("visual", Encoding::Iso8859_8),
+    ("csiso88598i", Encoding::Iso8859_8I),
+    ("iso-8859-8-i", Encoding::Iso8859_8I),
+    ("logical", Encoding::Iso8859_8I),
+    ("csisolatin6", Encoding::Iso8859_10),
+    ("iso-8859-10", Encoding::Iso8859_10),
+    ("iso-ir-157", Encoding::Iso8859_10),
+    ("iso8859-10", Encoding::Iso8859_10),
+    ("iso885910", Encoding::Iso8859_10),
+    ("l6", Encoding::Iso8859_10),
+    ("latin6", Encoding::Iso8859_10),
+    ("iso-8859-13", Encoding::Iso8859_13),
+    ("iso8859-13", Encoding::Iso8859_13),
+    ("iso885913", Encoding::Iso8859_13),
+    ("iso-8859-14", Encoding::Iso8859_14),
+    ("iso8859-14", Encoding::Iso8859_14),
+    ("iso885914", Encoding::Iso8859_14),
+    ("csisolatin9", Encoding::Iso8859_15),
+    ("iso-8859-15", Encoding::Iso8859_15),
+    ("iso8859-15", Encoding::Iso8859_15),
+    ("iso885915", Encoding::Iso8859_15),
+    ("iso_8859-15", Encoding::Iso8859_15),
+    ("l9", Encoding::Iso8859_15),
+    ("iso-8859-16", Encoding::Iso8859_16),
+];
+
+impl Encoding {
+    // Encoding named by a label such as "latin1", ignoring case and surrounding whitespace.
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        let label = label.trim_matches(|c| matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ')).to_ascii_lowercase();
+        LABELS.iter().find(|(name, _)| *name == label).map(|&(_, encoding)| encoding)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "windows-1252",
+            Encoding::Windows1254 => "windows-1254",
+            Encoding::Iso8859_2 => "ISO-8859-2",
+            Encoding::Iso8859_3 => "ISO-8859-3",
+            Encoding::Iso8859_4 => "ISO-8859-4",
+            Encoding::Iso8859_5 => "ISO-8859-5",
+            Encoding::Iso8859_6 => "ISO-8859-6",
+            Encoding::Iso8859_7 => "ISO-8859-7",
+            Encoding::Iso8859_8 => "ISO-8859-8",
+            Encoding::Iso8859_8I => "ISO-8859-8-I",
+            Encoding::Iso8859_10 => "ISO-8859-10",
+            Encoding::Iso8859_13 => "ISO-8859-13",
+            Encoding::Iso8859_14 => "ISO-8859-14",
+            Encoding::Iso8859_15 => "ISO-8859-15",
+            Encoding::Iso8859_16 => "ISO-8859-16",
+        }
+    }
+
+    // Characters of the bytes 0x80 to 0xFF, for single-byte encodings.
+    fn high_half(&self) -> Option<&'static [char; 128]> {
+        match self {
+            Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be => None,
+            Encoding::Windows1252 => Some(&tables::WINDOWS_1252),
+            Encoding::Windows1254 => Some(&tables::WINDOWS_1254),
+            Encoding::Iso8859_2 => Some(&tables::ISO_8859_2),
+            Encoding::Iso8859_3 => Some(&tables::ISO_8859_3),
+            Encoding::Iso8859_4 => Some(&tables::ISO_8859_4),
+            Encoding::Iso8859_5 => Some(&tables::ISO_8859_5),
+            Encoding::Iso8859_6 => Some(&tables::ISO_8859_6),
+            Encoding::Iso8859_7 => Some(&tables::ISO_8859_7),
+            Encoding::Iso8859_8 | Encoding::Iso8859_8I => Some(&tables::ISO_8859_8),
+            Encoding::Iso8859_10 => Some(&tables::ISO_8859_10),
+            Encoding::Iso8859_13 => Some(&tables::ISO_8859_13),
+            Encoding::Iso8859_14 => Some(&tables::ISO_8859_14),
+            Encoding::Iso8859_15 => Some(&tables::ISO_8859_15),
+            Encoding::Iso8859_16 => Some(&tables::ISO_8859_16),
+        }
+    }
+
+    // Decodes a whole input, replacing malformed sequences with U+FFFD.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        Decoder::new(*self).decode(bytes, true)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Decoder of an input received in several chunks, which keeps the bytes of
+// a character split between two chunks until the next one arrives.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    encoding: Encoding,
+    pending: Vec<u8>,
+    // High surrogate waiting for its low surrogate, in UTF-16.
+    high_surrogate: Option<u16>,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Decoder {
+        Decoder { encoding, pending: Vec::new(), high_surrogate: None }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    // Decodes the next chunk of the input. `last` tells that no chunk follows,
+    // so that an incomplete trailing sequence is replaced instead of kept.
+    pub fn decode(&mut self, bytes: &[u8], last: bool) -> String {
+        match self.encoding {
+            Encoding::Utf8 => self.decode_utf8(bytes, last),
+            Encoding::Utf16Le => self.decode_utf16(bytes, last, u16::from_le_bytes),
+            Encoding::Utf16Be => self.decode_utf16(bytes, last, u16::from_be_bytes),
+            _ => {
+                let high_half = self.encoding.high_half().unwrap();
+                bytes.iter()
+                     .map(|&b| if b < 0x80 { b as char } else { high_half[b as usize - 0x80] })
+                     .collect()
+            },
+        }
+    }
+
+    fn decode_utf8(&mut self, bytes: &[u8], last: bool) -> String {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+        let mut output = String::new();
+        let mut rest = &input[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    output.push_str(valid);
+                    break;
+                },
+                Err(error) => {
+                    let (valid, after_valid) = rest.split_at(error.valid_up_to());
+                    output.push_str(std::str::from_utf8(valid).unwrap());
+                    match error.error_len() {
+                        Some(len) => {
+                            output.push('\u{fffd}');
+                            rest = &after_valid[len..];
+                        },
+                        // The input ends in the middle of a character.
+                        None => {
+                            if last {
+                                output.push('\u{fffd}');
+                            } else {
+                                self.pending = after_valid.to_vec();
+                            }
+                            break;
+                        },
+                    }
+                },
+            }
+        }
+        output
+    }
+
+    fn decode_utf16(&mut self, bytes: &[u8], last: bool, to_unit: fn([u8; 2]) -> u16) -> String {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+        let mut output = String::new();
+        let mut units = input.chunks_exact(2);
+        for pair in &mut units {
+            let unit = to_unit([pair[0], pair[1]]);
+            match (self.high_surrogate.take(), unit) {
+                (Some(high), 0xDC00..=0xDFFF) => {
+                    let c = 0x10000 + ((high as u32 - 0xD800) << 10) + (unit as u32 - 0xDC00);
+                    output.push(char::from_u32(c).unwrap());
+                },
+                (high, _) => {
+                    if high.is_some() {
+                        output.push('\u{fffd}');
+                    }
+                    match unit {
+                        0xD800..=0xDBFF => self.high_surrogate = Some(unit),
+                        0xDC00..=0xDFFF => output.push('\u{fffd}'),
+                        _ => output.push(char::from_u32(unit as u32).unwrap()),
+                    }
+                },
+            }
+        }
+        self.pending = units.remainder().to_vec();
+        if last && (self.high_surrogate.take().is_some() || !self.pending.is_empty()) {
+            self.pending.clear();
+            output.push('\u{fffd}');
+        }
+        output
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+
+use crate::dom;
+use crate::source::Location;
+
+mod entities;
+mod parser;
+mod serializer;
+mod sniffer;
+mod tokenizer;
+mod tree_builder;
+
+pub use self::parser::{parse_reader, Parser};
+pub use self::tokenizer::{tokenize, Attribute, ClosedError, Doctype, Tag, TextKind, Token, Tokenizer};
+use self::tree_builder::TreeBuilder;
+pub use self::serializer::{inner_html, outer_html};
+pub use self::sniffer::sniff_encoding;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    // Where in the source the error was detected.
+    pub location: Location,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+// Parses an HTML document. Malformed input never makes the parser fail: it is
+// recovered from the way browsers do and reported in the returned list of errors.
+pub fn parse(source: String) -> (dom::Tree, Vec<ParseError>) {
+    let mut builder = TreeBuilder::new();
+    let errors = build(Tokenizer::new(&source), &mut builder);
+    (builder.into_tree(), errors)
+}
+
+// Parses an HTML document from its bytes, decoded with the encoding given by its
+// byte order mark or declared in a <meta>. The document records the encoding used.
+pub fn parse_bytes(bytes: &[u8]) -> (dom::Tree, Vec<ParseError>) {
+    // All the bytes are there, so unlike when they are pushed in chunks, a
+    // <meta> after the start of the body is looked for too.
+    let (encoding, bom_len) = sniff_encoding(bytes);
+    let mut parser = Parser::with_encoding(encoding);
+    parser.push(&bytes[bom_len..]);
+    parser.finish()
+}
+
+// Parses an HTML fragment as the content of the `context` element, like the
+// innerHTML setter does. The parsed nodes are the children of the root
+// DocumentFragment of the returned tree. A tree is returned rather than a list
+// of nodes because nodes only exist in the arena of a Tree, and link to their
+// children and siblings by NodeId in it.
+pub fn parse_fragment(source: String, context: &dom::ElementData) -> (dom::Tree, Vec<ParseError>) {
+    let mut tokenizer = Tokenizer::new(&source);
+    if context.is_html() {
+        if let Some(kind) = TextKind::of_element(&context.tag_name) {
+            tokenizer.start_text(kind);
+        }
+    }
+    let mut builder = TreeBuilder::new_fragment(context);
+    let errors = build(tokenizer, &mut builder);
+    (builder.into_fragment(), errors)
+}
+
+fn build(mut tokenizer: Tokenizer, builder: &mut TreeBuilder) -> Vec<ParseError> {
+    let mut errors = Vec::new();
+    process_tokens(&mut tokenizer, builder, &mut errors);
+    finish(&mut tokenizer, builder, &mut errors);
+    errors
+}
+
+// Builds the tree from the tokens available so far.
+fn process_tokens(tokenizer: &mut Tokenizer, builder: &mut TreeBuilder, errors: &mut Vec<ParseError>) {
+    loop {
+        // CDATA sections are only allowed in SVG and MathML content.
+        tokenizer.set_cdata_allowed(builder.in_foreign_content());
+        let token = match tokenizer.next() {
+            Some(token) => token,
+            None => break,
+        };
+        errors.append(&mut tokenizer.take_errors());
+        builder.process_token(token, tokenizer.span());
+        if let Some(kind) = builder.take_tokenizer_text() {
+            tokenizer.start_text(kind);
+        }
+        errors.append(&mut builder.take_errors());
+    }
+}
+
+// Called once all the tokens were processed.
+fn finish(tokenizer: &mut Tokenizer, builder: &mut TreeBuilder, errors: &mut Vec<ParseError>) {
+    errors.append(&mut tokenizer.take_errors());
+    builder.finish(tokenizer.end());
+    errors.append(&mut builder.take_errors());
+}
@@ -0,0 +1,153 @@
+use std::io;
+use std::io::Read;
+
+use crate::dom;
+use crate::encoding::{Decoder, Encoding};
+use crate::html::sniffer::{known_encoding, sniff_encoding};
+use crate::html::tree_builder::TreeBuilder;
+use crate::html::{finish, process_tokens, ParseError, Tokenizer};
+
+// Parser of a document received in chunks of bytes, e.g. from the network.
+// The tree is built as the chunks arrive, and the part built so far can be
+// looked at between two chunks for progressive rendering. Decoding starts
+// as soon as the encoding is known, see sniffer::known_encoding, and only the
+// input which is not tokenized yet is kept.
+pub struct Parser {
+    // Bytes received before the encoding is known.
+    undecoded: Vec<u8>,
+    decoder: Option<Decoder>,
+    tokenizer: Tokenizer,
+    builder: TreeBuilder,
+    errors: Vec<ParseError>,
+}
+
+impl Parser {
+    // Parser sniffing the encoding from the start of the document.
+    pub fn new() -> Parser {
+        Parser {
+            undecoded: Vec::new(),
+            decoder: None,
+            tokenizer: Tokenizer::incremental(),
+            builder: TreeBuilder::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    // Parser decoding the document with a known encoding, e.g. given by the
+    // Content-Type of an HTTP response.
+    pub fn with_encoding(encoding: Encoding) -> Parser {
+        let mut parser = Parser::new();
+        parser.set_encoding(encoding);
+        parser
+    }
+
+    // Sets the decoder, and the encoding recorded by the document.
+    fn set_encoding(&mut self, encoding: Encoding) {
+        if let dom::NodeType::Document(data) = &mut self.builder.tree_mut()[dom::Tree::ROOT].node_type {
+            data.encoding = encoding;
+        }
+        self.decoder = Some(Decoder::new(encoding));
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        match &mut self.decoder {
+            Some(decoder) => self.tokenizer.push_input(&decoder.decode(bytes, false)),
+            None => {
+                self.undecoded.extend_from_slice(bytes);
+                if let Some((encoding, bom_len)) = known_encoding(&self.undecoded) {
+                    self.start_decoding(encoding, bom_len);
+                }
+            },
+        }
+        process_tokens(&mut self.tokenizer, &mut self.builder, &mut self.errors);
+    }
+
+    fn start_decoding(&mut self, encoding: Encoding, bom_len: usize) {
+        self.set_encoding(encoding);
+        let chars = self.decoder.as_mut().unwrap().decode(&self.undecoded[bom_len..], false);
+        self.tokenizer.push_input(&chars);
+        self.undecoded = Vec::new();
+    }
+
+    // Errors found so far, which are not returned again by `finish`.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    // Tree built from the chunks received so far. It has all the tokens
+    // complete in them: only a tag, comment or character reference cut by the
+    // end of the last chunk is left for the next one.
+    pub fn tree(&self) -> &dom::Tree {
+        self.builder.tree()
+    }
+
+    // Parses the end of the document once all the chunks were pushed.
+    pub fn finish(mut self) -> (dom::Tree, Vec<ParseError>) {
+        if self.decoder.is_none() {
+            let (encoding, bom_len) = sniff_encoding(&self.undecoded);
+            self.start_decoding(encoding, bom_len);
+        }
+        let last_chars = self.decoder.as_mut().unwrap().decode(&[], true);
+        self.tokenizer.push_input(&last_chars);
+        self.tokenizer.close();
+        process_tokens(&mut self.tokenizer, &mut self.builder, &mut self.errors);
+        finish(&mut self.tokenizer, &mut self.builder, &mut self.errors);
+        (self.builder.into_tree(), self.errors)
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        Parser::new()
+    }
+}
+
+// Parses a document read in chunks from `reader`.
+pub fn parse_reader<R: Read>(mut reader: R) -> io::Result<(dom::Tree, Vec<ParseError>)> {
+    let mut parser = Parser::new();
+    let mut buffer = [0; 8192];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(parser.finish()),
+            Ok(len) => parser.push(&buffer[..len]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parser;
+    use crate::dom;
+    use crate::html::{parse, ParseError};
+
+    fn parse_chunks(chunks: &[&[u8]]) -> (dom::Tree, Vec<ParseError>) {
+        let mut parser = Parser::new();
+        for chunk in chunks {
+            parser.push(chunk);
+        }
+        parser.finish()
+    }
+
+    fn assert_same_parse(parsed: (dom::Tree, Vec<ParseError>), whole: &(dom::Tree, Vec<ParseError>), what: &str) {
+        assert_eq!(parsed.0.to_string(), whole.0.to_string(), "tree of {}", what);
+        assert_eq!(parsed.1, whole.1, "errors of {}", what);
+    }
+
+    #[test]
+    fn chunks_are_parsed_like_the_whole_document() {
+        let source = "<!DOCTYPE html><meta charset=utf-8><title>é &amp; t</title>\r\n\
+                      <p class='a b'>ligne é &#x41;&copy x<!-- c -->\ry</b><span title='a&ampb'>日本</span>\n\
+                      <table><tr><td>a</td>b</table><svg><![CDATA[x]]></svg>\
+                      <script>if (a < b) {}</script><textarea>\nq</textarea>&notit;<";
+        let whole = parse(source.to_string());
+        let bytes = source.as_bytes();
+        for i in 0..=bytes.len() {
+            let (before, after) = bytes.split_at(i);
+            assert_same_parse(parse_chunks(&[before, after]), &whole, &format!("the split at {}", i));
+        }
+        let single_bytes: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_same_parse(parse_chunks(&single_bytes), &whole, "single bytes");
+    }
+}
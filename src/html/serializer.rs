@@ -0,0 +1,158 @@
+use crate::dom::{DoctypeData, NodeRef, NodeType};
+use crate::html::tree_builder::VOID_ELEMENTS;
+
+// Serialization of HTML fragments, see
+// https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+
+// Elements whose text content is written without escaping.
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "iframe", "noembed", "noframes", "noscript", "plaintext", "script", "style", "xmp",
+];
+
+// Attribute values also escape '"'. They escape '<' and '>' too, as browsers
+// now do, so that the markup of a value is never mistaken for a tag.
+fn escape(text: &str, in_attribute: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if in_attribute => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn serialize_node(node: NodeRef, out: &mut String) {
+    match &node.node_type {
+        NodeType::Document(_) | NodeType::DocumentFragment => serialize_children(node, out),
+        NodeType::Doctype(DoctypeData{name, ..}) => {
+            out.push_str("<!DOCTYPE ");
+            out.push_str(name);
+            out.push('>');
+        },
+        NodeType::Comment(text) => {
+            out.push_str("<!--");
+            out.push_str(text);
+            out.push_str("-->");
+        },
+        NodeType::Text(text) => match node.parent().and_then(|parent| parent.element()) {
+            Some(parent) if parent.is_html() && RAW_TEXT_ELEMENTS.contains(&parent.tag_name.as_str()) => out.push_str(text),
+            _ => escape(text, false, out),
+        },
+        NodeType::Element(element) => {
+            out.push('<');
+            out.push_str(&element.tag_name);
+            for (name, value) in element.attributes().iter() {
+                out.push(' ');
+                out.push_str(name);
+                out.push_str("=\"");
+                escape(value, true, out);
+                out.push('"');
+            }
+            out.push('>');
+            if element.is_html() && VOID_ELEMENTS.contains(&element.tag_name.as_str()) {
+                return;
+            }
+            serialize_children(node, out);
+            out.push_str("</");
+            out.push_str(&element.tag_name);
+            out.push('>');
+        },
+    }
+}
+
+fn serialize_children(node: NodeRef, out: &mut String) {
+    let name = node.element().filter(|element| element.is_html()).map(|element| element.tag_name.as_str());
+    // The parser drops a newline right after these start tags, so a leading
+    // newline of the content has to be doubled to survive a round trip.
+    if let (Some("pre" | "textarea" | "listing"), Some(NodeType::Text(text))) =
+        (name, node.first_child().map(|child| &child.node().node_type)) {
+        if text.starts_with('\n') {
+            out.push('\n');
+        }
+    }
+    for child in node.children() {
+        serialize_node(child, out);
+    }
+}
+
+// HTML serialization of the node and its descendants.
+pub fn outer_html(node: NodeRef) -> String {
+    let mut html = String::new();
+    serialize_node(node, &mut html);
+    html
+}
+
+// HTML serialization of the descendants of the node.
+pub fn inner_html(node: NodeRef) -> String {
+    let mut html = String::new();
+    serialize_children(node, &mut html);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::inner_html;
+    use crate::dom::NodeRef;
+    use crate::html::parse;
+
+    fn child<'a>(node: NodeRef<'a>, tag_name: &str) -> NodeRef<'a> {
+        node.children().find(|child| child.element().is_some_and(|element| element.tag_name == tag_name)).unwrap()
+    }
+
+    // Parses `source` as the content of the <body> and serializes it back.
+    fn reserialize(source: &str) -> String {
+        let (document, _) = parse(format!("<body>{}", source));
+        inner_html(child(child(document.root(), "html"), "body"))
+    }
+
+    // Checks the serialization of `source`, and that parsing it again gives
+    // the same tree.
+    fn assert_round_trip(source: &str, expected: &str) {
+        let serialized = reserialize(source);
+        assert_eq!(serialized, expected);
+        assert_eq!(reserialize(&serialized), serialized);
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_round_trip("<p>a &amp; b &lt; c &gt; d&nbsp;e \"f\"</p>", "<p>a &amp; b &lt; c &gt; d&nbsp;e \"f\"</p>");
+        assert_round_trip("a & b < c", "a &amp; b &lt; c");
+    }
+
+    #[test]
+    fn escapes_attribute_values() {
+        assert_round_trip("<p title='&amp; \"a\" <b>&nbsp;'></p>",
+                          "<p title=\"&amp; &quot;a&quot; &lt;b&gt;&nbsp;\"></p>");
+        assert_round_trip("<a href=\"?a=1&b=<2>\" data-x='</a><script>'>x</a>",
+                          "<a href=\"?a=1&amp;b=&lt;2&gt;\" data-x=\"&lt;/a&gt;&lt;script&gt;\">x</a>");
+        // The quotes of the other kind are kept.
+        assert_round_trip("<p title=\"'a'\" lang=a'b></p>", "<p title=\"'a'\" lang=\"a'b\"></p>");
+    }
+
+    #[test]
+    fn void_elements_have_no_end_tag() {
+        assert_round_trip("<br><img src=a.png><input disabled>", "<br><img src=\"a.png\"><input disabled=\"\">");
+        assert_round_trip("<p>a<br/>b</p><hr>", "<p>a<br>b</p><hr>");
+    }
+
+    #[test]
+    fn raw_text_is_not_escaped() {
+        assert_round_trip("<style>a > b { content: \"&amp;\" }</style>", "<style>a > b { content: \"&amp;\" }</style>");
+        assert_round_trip("<script>if (a < b && c) {}</script>", "<script>if (a < b && c) {}</script>");
+        // RCDATA content is escaped like any text.
+        assert_round_trip("<textarea>a < b &amp; <p></textarea>", "<textarea>a &lt; b &amp; &lt;p&gt;</textarea>");
+    }
+
+    #[test]
+    fn leading_newline_of_pre_and_textarea() {
+        // The first newline after the start tag is dropped by the parser.
+        assert_round_trip("<pre>\nx</pre>", "<pre>x</pre>");
+        assert_round_trip("<textarea>\nx</textarea>", "<textarea>x</textarea>");
+        // A newline left at the start of the content is doubled.
+        assert_round_trip("<pre>\n\nx</pre>", "<pre>\n\nx</pre>");
+        assert_round_trip("<textarea>\n\nx</textarea>", "<textarea>\n\nx</textarea>");
+    }
+}
@@ -0,0 +1,356 @@
+use crate::encoding::Encoding;
+
+// Encoding sniffing algorithm of the HTML specification:
+// https://html.spec.whatwg.org/multipage/parsing.html#encoding-sniffing-algorithm
+
+// Number of bytes looked at for a <meta> declaring the encoding.
+pub const PRESCAN_LEN: usize = 1024;
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes.get(from..)?
+         .windows(needle.len())
+         .position(|window| window == needle)
+         .map(|index| index + from)
+}
+
+// Elements which may come before a <meta> declaring the encoding. When the
+// document is received in chunks, the prescan stops at other start tags: like
+// in browsers, a <meta> after the start of the body is not waited for, so that
+// the encoding is known early.
+const HEAD_ELEMENTS: &[&[u8]] = &[
+    b"base", b"head", b"html", b"link", b"meta", b"noscript", b"script", b"style", b"template", b"title",
+];
+
+// Returns the encoding of a document, and the length of its byte order mark.
+pub fn sniff_encoding(bytes: &[u8]) -> (Encoding, usize) {
+    if let Some(found) = byte_order_mark(bytes) {
+        return found;
+    }
+    let start = &bytes[..bytes.len().min(PRESCAN_LEN)];
+    match prescan(start, false) {
+        Prescan::Found(encoding) => (encoding, 0),
+        Prescan::EndOfHead | Prescan::EndOfInput => (default_encoding(start), 0),
+    }
+}
+
+// Encoding of a document of which only the first bytes were received, once
+// they are enough to know it: after a byte order mark, a <meta> declaring the
+// encoding, the end of the head or the first PRESCAN_LEN bytes.
+pub(crate) fn known_encoding(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    if let Some(found) = byte_order_mark(bytes) {
+        return Some(found);
+    }
+    // The start of a byte order mark.
+    if bytes.len() < 3 && [&b"\xEF\xBB\xBF"[..], b"\xFE\xFF", b"\xFF\xFE"].iter().any(|bom| bom.starts_with(bytes)) {
+        return None;
+    }
+    let start = &bytes[..bytes.len().min(PRESCAN_LEN)];
+    match prescan(start, true) {
+        Prescan::Found(encoding) => Some((encoding, 0)),
+        Prescan::EndOfHead => Some((default_encoding(start), 0)),
+        Prescan::EndOfInput if bytes.len() >= PRESCAN_LEN => Some((default_encoding(start), 0)),
+        Prescan::EndOfInput => None,
+    }
+}
+
+fn byte_order_mark(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    if bytes.starts_with(b"\xEF\xBB\xBF") {
+        Some((Encoding::Utf8, 3))
+    } else if bytes.starts_with(b"\xFE\xFF") {
+        Some((Encoding::Utf16Be, 2))
+    } else if bytes.starts_with(b"\xFF\xFE") {
+        Some((Encoding::Utf16Le, 2))
+    } else {
+        None
+    }
+}
+
+// Without any declaration, UTF-8 is used if the start of the document is
+// valid UTF-8 (the last character may be cut), and windows-1252 otherwise.
+// A document received in chunks is decoded as soon as its head ended, so only
+// the bytes received by then are looked at.
+fn default_encoding(bytes: &[u8]) -> Encoding {
+    let start = &bytes[..bytes.len().min(PRESCAN_LEN)];
+    match std::str::from_utf8(start) {
+        Err(error) if error.error_len().is_some() => Encoding::Windows1252,
+        _ => Encoding::Utf8,
+    }
+}
+
+enum Prescan {
+    Found(Encoding),
+    // A start tag which cannot be in the head was found.
+    EndOfHead,
+    // The bytes ended, possibly in the middle of a tag or comment.
+    EndOfInput,
+}
+
+// Looks for a <meta> declaring the encoding, skipping comments and other tags.
+// With `stop_at_body`, gives up at the first start tag which cannot be in the head.
+fn prescan(bytes: &[u8], stop_at_body: bool) -> Prescan {
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"<!--") {
+            // The dashes of "<!--" may also end the comment, as in "<!-->".
+            pos = match find(bytes, pos + 2, b"-->") {
+                Some(end) => end + 2,
+                None => return Prescan::EndOfInput,
+            };
+        } else if starts_with_ignore_case(rest, b"<meta") && rest.get(5).is_some_and(|&b| is_space(b) || b == b'/') {
+            pos += 6;
+            let encoding = meta_encoding(bytes, &mut pos);
+            // The attributes of the <meta> may go on after the bytes received.
+            if pos >= bytes.len() {
+                return Prescan::EndOfInput;
+            }
+            if let Some(encoding) = encoding {
+                return Prescan::Found(encoding);
+            }
+        } else if rest.len() > 2 && rest[0] == b'<'
+            && (rest[1].is_ascii_alphabetic() || (rest[1] == b'/' && rest[2].is_ascii_alphabetic())) {
+            let name_end = match bytes[pos..].iter().position(|&b| is_space(b) || b == b'>' || b == b'/') {
+                Some(len) => pos + len,
+                None => return Prescan::EndOfInput,
+            };
+            if stop_at_body && rest[1] != b'/' && !HEAD_ELEMENTS.iter().any(|name| bytes[pos + 1..name_end].eq_ignore_ascii_case(name)) {
+                return Prescan::EndOfHead;
+            }
+            pos = match bytes[pos..].iter().position(|&b| is_space(b) || b == b'>') {
+                Some(len) => pos + len,
+                None => return Prescan::EndOfInput,
+            };
+            while get_attribute(bytes, &mut pos).is_some() {}
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            pos = match bytes[pos..].iter().position(|&b| b == b'>') {
+                Some(len) => pos + len,
+                None => return Prescan::EndOfInput,
+            };
+        }
+        pos += 1;
+    }
+    Prescan::EndOfInput
+}
+
+// Encoding declared by the attributes of a <meta>, which start at `pos`.
+fn meta_encoding(bytes: &[u8], pos: &mut usize) -> Option<Encoding> {
+    let mut seen = Vec::new();
+    let mut got_pragma = false;
+    let mut need_pragma = None;
+    let mut charset = None;
+    while let Some((name, value)) = get_attribute(bytes, pos) {
+        if seen.contains(&name) {
+            continue;
+        }
+        match name.as_slice() {
+            b"http-equiv" if value == b"content-type" => got_pragma = true,
+            b"content" if charset.is_none() => {
+                if let Some(encoding) = encoding_from_content(&value) {
+                    charset = Some(encoding);
+                    need_pragma = Some(true);
+                }
+            },
+            b"charset" => {
+                charset = Encoding::for_label(&String::from_utf8_lossy(&value));
+                need_pragma = Some(false);
+            },
+            _ => {},
+        }
+        seen.push(name);
+    }
+    match (need_pragma, charset) {
+        (Some(true), _) if !got_pragma => None,
+        // A document read as bytes cannot be UTF-16 if the <meta> could be read as ASCII.
+        (Some(_), Some(Encoding::Utf16Le | Encoding::Utf16Be)) => Some(Encoding::Utf8),
+        (Some(_), charset) => charset,
+        (None, _) => None,
+    }
+}
+
+// Reads the attribute at `pos`, lowercased, and moves past it. Returns None
+// at the end of the tag, leaving `pos` on the '>'.
+fn get_attribute(bytes: &[u8], pos: &mut usize) -> Option<(Vec<u8>, Vec<u8>)> {
+    while *pos < bytes.len() && (is_space(bytes[*pos]) || bytes[*pos] == b'/') {
+        *pos += 1;
+    }
+    let mut name = Vec::new();
+    loop {
+        match *bytes.get(*pos)? {
+            b'>' if name.is_empty() => return None,
+            b'=' if !name.is_empty() => {
+                *pos += 1;
+                break;
+            },
+            b if is_space(b) => {
+                while is_space(*bytes.get(*pos)?) {
+                    *pos += 1;
+                }
+                if bytes[*pos] != b'=' {
+                    return Some((name, Vec::new()));
+                }
+                *pos += 1;
+                break;
+            },
+            b'/' | b'>' => return Some((name, Vec::new())),
+            b => {
+                name.push(b.to_ascii_lowercase());
+                *pos += 1;
+            },
+        }
+    }
+    while is_space(*bytes.get(*pos)?) {
+        *pos += 1;
+    }
+    let mut value = Vec::new();
+    match *bytes.get(*pos)? {
+        quote @ (b'"' | b'\'') => {
+            *pos += 1;
+            loop {
+                let b = *bytes.get(*pos)?;
+                *pos += 1;
+                if b == quote {
+                    return Some((name, value));
+                }
+                value.push(b.to_ascii_lowercase());
+            }
+        },
+        b'>' => Some((name, value)),
+        _ => {
+            loop {
+                let b = *bytes.get(*pos)?;
+                if is_space(b) || b == b'>' {
+                    return Some((name, value));
+                }
+                value.push(b.to_ascii_lowercase());
+                *pos += 1;
+            }
+        },
+    }
+}
+
+// Encoding named in the content attribute of a <meta http-equiv>, e.g.
+// "text/html; charset=iso-8859-2".
+fn encoding_from_content(content: &[u8]) -> Option<Encoding> {
+    let mut pos = 0;
+    loop {
+        pos = find(content, pos, b"charset")? + b"charset".len();
+        while content.get(pos).is_some_and(|&b| is_space(b)) {
+            pos += 1;
+        }
+        if content.get(pos) == Some(&b'=') {
+            break;
+        }
+    }
+    pos += 1;
+    while content.get(pos).is_some_and(|&b| is_space(b)) {
+        pos += 1;
+    }
+    let label = match *content.get(pos)? {
+        quote @ (b'"' | b'\'') => {
+            let end = find(content, pos + 1, &[quote])?;
+            &content[pos + 1..end]
+        },
+        _ => {
+            let end = content[pos..].iter()
+                                    .position(|&b| is_space(b) || b == b';')
+                                    .map_or(content.len(), |index| index + pos);
+            &content[pos..end]
+        },
+    };
+    Encoding::for_label(&String::from_utf8_lossy(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{known_encoding, sniff_encoding, PRESCAN_LEN};
+    use crate::encoding::Encoding;
+
+    fn sniffed(bytes: &[u8]) -> Encoding {
+        let (encoding, bom_len) = sniff_encoding(bytes);
+        assert_eq!(bom_len, 0);
+        encoding
+    }
+
+    #[test]
+    fn byte_order_marks() {
+        assert_eq!(sniff_encoding(b"\xEF\xBB\xBF<meta charset=iso-8859-2>"), (Encoding::Utf8, 3));
+        assert_eq!(sniff_encoding(b"\xFE\xFF\0<"), (Encoding::Utf16Be, 2));
+        assert_eq!(sniff_encoding(b"\xFF\xFE<\0"), (Encoding::Utf16Le, 2));
+        // A byte order mark may still be completed by the next bytes.
+        assert_eq!(known_encoding(b"\xEF\xBB"), None);
+        assert_eq!(known_encoding(b"\xFE"), None);
+        assert_eq!(known_encoding(b"\xEF\xBB\xBF"), Some((Encoding::Utf8, 3)));
+        assert_eq!(sniff_encoding(b"\xEF\xBB"), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn meta_charset() {
+        assert_eq!(sniffed(b"<meta charset=iso-8859-2>"), Encoding::Iso8859_2);
+        assert_eq!(sniffed(b"<!-- <meta charset=latin2> --><META CHARSET='Latin2'>"), Encoding::Iso8859_2);
+        assert_eq!(sniffed(b"<html lang=\"<meta charset=latin2>\"><meta/charset=\" windows-1254 \">"), Encoding::Windows1254);
+        assert_eq!(sniffed(b"<meta charset=windows-1254 charset=latin2>"), Encoding::Windows1254);
+        // An unknown label is ignored, and so is a <meta> after the first PRESCAN_LEN bytes.
+        assert_eq!(sniffed(b"<meta charset=unknown>\xE9."), Encoding::Windows1252);
+        let late_meta = [&[b' '; PRESCAN_LEN][..], b"<meta charset=latin2>"].concat();
+        assert_eq!(sniffed(&late_meta), Encoding::Utf8);
+    }
+
+    #[test]
+    fn meta_http_equiv_content() {
+        assert_eq!(sniffed(b"<meta http-equiv=Content-Type content='text/html; charset=iso-8859-2'>"),
+                   Encoding::Iso8859_2);
+        assert_eq!(sniffed(b"<meta content=\"text/html;charset = 'latin2'\" http-equiv=\"content-type\">"),
+                   Encoding::Iso8859_2);
+        // The content is only used with the pragma.
+        assert_eq!(sniffed(b"<meta content='text/html; charset=iso-8859-2'>"), Encoding::Utf8);
+        assert_eq!(sniffed(b"<meta http-equiv=refresh content='charset=iso-8859-2'>"), Encoding::Utf8);
+        // A content without a charset leaves the charset attribute to be used.
+        assert_eq!(sniffed(b"<meta http-equiv=content-type content=text/html charset=latin2>"), Encoding::Iso8859_2);
+    }
+
+    #[test]
+    fn utf_16_declarations_mean_utf_8() {
+        assert_eq!(sniffed(b"<meta charset=utf-16>"), Encoding::Utf8);
+        assert_eq!(sniffed(b"<meta charset=utf-16be>\xE9"), Encoding::Utf8);
+        assert_eq!(sniffed(b"<meta http-equiv=content-type content='text/html; charset=utf-16le'>"), Encoding::Utf8);
+    }
+
+    #[test]
+    fn fallback_without_declaration() {
+        assert_eq!(sniffed(b""), Encoding::Utf8);
+        assert_eq!(sniffed("<p>é</p>".as_bytes()), Encoding::Utf8);
+        assert_eq!(sniffed(b"<p>\xE9</p>"), Encoding::Windows1252);
+        // A character cut at the end of the bytes looked at is valid.
+        assert_eq!(sniffed(b"<p>\xC3"), Encoding::Utf8);
+    }
+
+    #[test]
+    fn known_encoding_waits_for_a_declaration() {
+        assert_eq!(known_encoding(b"<html><head><title>a</title>"), None);
+        assert_eq!(known_encoding(b"<html><head><meta charset=latin2"), None);
+        assert_eq!(known_encoding(b"<html><head><meta charset=latin2>"), Some((Encoding::Iso8859_2, 0)));
+        assert_eq!(known_encoding(b"<!-- <p> "), None);
+        assert_eq!(known_encoding(b"<html><p>\xE9</p>"), Some((Encoding::Windows1252, 0)));
+        let long_head = [&b"<head>"[..], &[b' '; PRESCAN_LEN]].concat();
+        assert_eq!(known_encoding(&long_head), Some((Encoding::Utf8, 0)));
+    }
+
+    #[test]
+    fn meta_after_the_start_of_the_body() {
+        let source = b"<html><body><p>a</p><meta charset=windows-1252>";
+        // With all the bytes, the <meta> is found...
+        assert_eq!(sniff_encoding(source), (Encoding::Windows1252, 0));
+        // ...while bytes received in chunks are decoded from the start of the body.
+        assert_eq!(known_encoding(source), Some((Encoding::Utf8, 0)));
+        assert_eq!(known_encoding(b"<html><head><title>a</title><meta charset=windows-1252>"),
+                   Some((Encoding::Windows1252, 0)));
+    }
+}
@@ -0,0 +1,1669 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::html::entities::{LONGEST_NAME_LEN, NAMED_CHARACTER_REFERENCES};
+use crate::html::ParseError;
+use crate::source::{LineIndex, Location, Span};
+
+// Tokenizer following the states of the WHATWG HTML specification:
+// https://html.spec.whatwg.org/multipage/parsing.html#tokenization
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Doctype(Doctype),
+    StartTag(Tag),
+    EndTag(Tag),
+    Comment(String),
+    Character(char),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub attributes: Vec<Attribute>,
+    pub self_closing: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Doctype {
+    pub name: Option<String>,
+    pub public_id: Option<String>,
+    pub system_id: Option<String>,
+    pub force_quirks: bool,
+}
+
+// Content of elements which is tokenized as text rather than as markup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextKind {
+    // Text with character references, e.g. <title> and <textarea>.
+    Rcdata,
+    // Text without character references, e.g. <style>.
+    Rawtext,
+    ScriptData,
+    // Everything up to the end of the file.
+    Plaintext,
+}
+
+impl TextKind {
+    // Kind of text content of the element, when parsed outside of foreign content.
+    pub fn of_element(tag_name: &str) -> Option<TextKind> {
+        match tag_name {
+            "title" | "textarea" => Some(TextKind::Rcdata),
+            "style" | "xmp" | "iframe" | "noembed" | "noframes" | "noscript" => Some(TextKind::Rawtext),
+            "script" => Some(TextKind::ScriptData),
+            "plaintext" => Some(TextKind::Plaintext),
+            _ => None,
+        }
+    }
+}
+
+// Text states which may be ended by an end tag. ScriptDataEscaped is the
+// content of "<!--" in a script.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextState {
+    Rcdata,
+    Rawtext,
+    ScriptData,
+    ScriptDataEscaped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Data,
+    Text(TextState),
+    Plaintext,
+    TextLessThanSign(TextState),
+    TextEndTagOpen(TextState),
+    TextEndTagName(TextState),
+    ScriptDataEscapeStart,
+    ScriptDataEscapeStartDash,
+    ScriptDataEscapedDash,
+    ScriptDataEscapedDashDash,
+    ScriptDataDoubleEscapeStart,
+    ScriptDataDoubleEscaped,
+    ScriptDataDoubleEscapedDash,
+    ScriptDataDoubleEscapedDashDash,
+    ScriptDataDoubleEscapedLessThanSign,
+    ScriptDataDoubleEscapeEnd,
+    TagOpen,
+    EndTagOpen,
+    TagName,
+    BeforeAttributeName,
+    AttributeName,
+    AfterAttributeName,
+    BeforeAttributeValue,
+    // The char is the quote delimiting the value.
+    AttributeValueQuoted(char),
+    AttributeValueUnquoted,
+    AfterAttributeValueQuoted,
+    SelfClosingStartTag,
+    BogusComment,
+    MarkupDeclarationOpen,
+    CommentStart,
+    CommentStartDash,
+    Comment,
+    CommentEndDash,
+    CommentEnd,
+    CommentEndBang,
+    Doctype,
+    BeforeDoctypeName,
+    DoctypeName,
+    AfterDoctypeName,
+    AfterDoctypePublicKeyword,
+    BeforeDoctypePublicIdentifier,
+    DoctypePublicIdentifierQuoted(char),
+    AfterDoctypePublicIdentifier,
+    BetweenDoctypePublicAndSystemIdentifiers,
+    AfterDoctypeSystemKeyword,
+    BeforeDoctypeSystemIdentifier,
+    DoctypeSystemIdentifierQuoted(char),
+    AfterDoctypeSystemIdentifier,
+    BogusDoctype,
+    CdataSection,
+    CdataSectionBracket,
+    CdataSectionEnd,
+}
+
+// Replacements of numeric character references to C1 control characters,
+// which legacy content uses to mean Windows-1252 characters.
+const C1_REPLACEMENTS: &[(u32, char)] = &[
+    (0x80, '\u{20ac}'), (0x82, '\u{201a}'), (0x83, '\u{0192}'), (0x84, '\u{201e}'),
+    (0x85, '\u{2026}'), (0x86, '\u{2020}'), (0x87, '\u{2021}'), (0x88, '\u{02c6}'),
+    (0x89, '\u{2030}'), (0x8a, '\u{0160}'), (0x8b, '\u{2039}'), (0x8c, '\u{0152}'),
+    (0x8e, '\u{017d}'), (0x91, '\u{2018}'), (0x92, '\u{2019}'), (0x93, '\u{201c}'),
+    (0x94, '\u{201d}'), (0x95, '\u{2022}'), (0x96, '\u{2013}'), (0x97, '\u{2014}'),
+    (0x98, '\u{02dc}'), (0x99, '\u{2122}'), (0x9a, '\u{0161}'), (0x9b, '\u{203a}'),
+    (0x9c, '\u{0153}'), (0x9e, '\u{017e}'), (0x9f, '\u{0178}'),
+];
+
+fn is_noncharacter(code: u32) -> bool {
+    (0xfdd0..=0xfdef).contains(&code) || (code & 0xfffe) == 0xfffe
+}
+
+// Finds the longest named character reference at the start of `input`.
+fn match_named_character_reference(input: &str) -> Option<(&'static str, &'static str)> {
+    let name_len = input.char_indices()
+                        .take(LONGEST_NAME_LEN)
+                        .find(|(_, c)| !c.is_ascii_alphanumeric())
+                        .map_or(input.len().min(LONGEST_NAME_LEN), |(i, _)| i);
+    let lookup = |name: &str| NAMED_CHARACTER_REFERENCES.binary_search_by(|(entry, _)| entry.cmp(&name))
+                                                        .ok()
+                                                        .map(|index| NAMED_CHARACTER_REFERENCES[index]);
+    if input[name_len..].starts_with(';') {
+        if let Some(found) = lookup(&input[..name_len + 1]) {
+            return Some(found);
+        }
+    }
+    // Only the legacy names, which do not end with ';', can be a prefix of
+    // the alphanumeric run.
+    (1..=name_len).rev().find_map(|len| lookup(&input[..len]))
+}
+
+// Bytes of input which must be available after the current position before
+// taking a step while more input may come, enough for the longest lookahead
+// (a named character reference and the character after it).
+const LOOKAHEAD: usize = LONGEST_NAME_LEN + 8;
+
+// Error of Tokenizer::feed called after Tokenizer::close.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosedError;
+
+impl fmt::Display for ClosedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "input fed to a closed tokenizer")
+    }
+}
+
+// Whether `rest` is the start of one of the keywords, which the next chunk may complete.
+fn is_cut_keyword(rest: &str, keywords: &[&str]) -> bool {
+    keywords.iter().any(|keyword| {
+        rest.len() < keyword.len() && keyword.as_bytes()[..rest.len()].eq_ignore_ascii_case(rest.as_bytes())
+    })
+}
+
+pub struct Tokenizer {
+    // Input received and not consumed yet, offsets are relative to its start.
+    input: String,
+    // Length of the consumed input dropped from the start of `input`.
+    drained: usize,
+    lines: LineIndex,
+    // Set once all the input was received.
+    closed: bool,
+    pos: usize,
+    // Length in bytes of the last consumed character, to be able to reconsume it.
+    last_len: usize,
+    state: State,
+    finished: bool,
+
+    // Token being built, and the offset at which it started.
+    current_tag: Tag,
+    current_tag_is_end: bool,
+    current_attribute: Option<Attribute>,
+    current_comment: String,
+    current_doctype: Doctype,
+    token_start: usize,
+
+    // Tokens ready to be returned, with the offsets in the document at which
+    // they start and end.
+    pending: VecDeque<(Token, usize, usize)>,
+    last_span: Span,
+    errors: Vec<ParseError>,
+
+    // Name of the last emitted start tag, which text content ends with.
+    last_start_tag_name: String,
+    temporary_buffer: String,
+    // Set by the tree builder in SVG and MathML content, where <![CDATA[ starts
+    // a CDATA section rather than a bogus comment.
+    cdata_allowed: bool,
+}
+
+impl Tokenizer {
+    pub fn new(source: &str) -> Tokenizer {
+        let mut tokenizer = Tokenizer::incremental();
+        tokenizer.push_input(source);
+        tokenizer.close();
+        tokenizer
+    }
+
+    // Tokenizer of an input received in chunks with `feed`. Until `close` is
+    // called, it stops returning tokens when it reaches the end of the input
+    // received so far, and resumes once more input is fed.
+    pub fn incremental() -> Tokenizer {
+        Tokenizer {
+            input: String::new(),
+            drained: 0,
+            lines: LineIndex::new(""),
+            closed: false,
+            pos: 0,
+            last_len: 0,
+            state: State::Data,
+            finished: false,
+            current_tag: Tag::default(),
+            current_tag_is_end: false,
+            current_attribute: None,
+            current_comment: String::new(),
+            current_doctype: Doctype::default(),
+            token_start: 0,
+            pending: VecDeque::new(),
+            last_span: Span::at(Location::start()),
+            errors: Vec::new(),
+            last_start_tag_name: String::new(),
+            temporary_buffer: String::new(),
+            cdata_allowed: false,
+        }
+    }
+
+    // Appends a chunk to the input. Fails once `close` was called, as the end
+    // of the file may already have been tokenized.
+    pub fn feed(&mut self, chunk: &str) -> Result<(), ClosedError> {
+        if self.closed {
+            return Err(ClosedError);
+        }
+        self.push_input(chunk);
+        Ok(())
+    }
+
+    // Like `feed`, for callers which never feed a closed tokenizer.
+    pub(crate) fn push_input(&mut self, chunk: &str) {
+        self.drain_consumed();
+        self.input.push_str(chunk);
+        self.lines.update(&self.input);
+    }
+
+    // Drops the consumed input, so that only the part of the document which
+    // is not tokenized yet is kept in memory. Inside a tag or a comment, or
+    // while tokens are waiting to be returned, the input is kept as is.
+    fn drain_consumed(&mut self) {
+        let in_text = matches!(self.state, State::Data | State::Text(_) | State::Plaintext | State::CdataSection);
+        if in_text && self.pos > 0 && self.pending.is_empty() {
+            let len = self.lines.drain(&mut self.input, self.pos);
+            self.pos -= len;
+            self.drained += len;
+        }
+    }
+
+    // Tells that all the input was fed.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    // Whether all the tokens were returned.
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.pending.is_empty()
+    }
+
+    // Whether enough input is available to take the next step without
+    // mistaking the end of the input received so far for the end of the file.
+    fn can_step(&self) -> bool {
+        if self.closed {
+            return true;
+        }
+        let rest = &self.input[self.pos..];
+        if rest.len() < LOOKAHEAD {
+            // Near the end of the input received so far, only the steps which
+            // look at a single character are taken, so that text is tokenized
+            // as soon as it is received.
+            return match rest.chars().next() {
+                None => false,
+                // "\r\n" is a single newline.
+                Some('\r') => rest.len() > 1,
+                // The longest matching reference is known once its name ended.
+                Some('&') => rest[1..].contains(|c: char| !c.is_ascii_alphanumeric() && c != '#'),
+                Some(_) => match self.state {
+                    State::MarkupDeclarationOpen => !is_cut_keyword(rest, &["--", "DOCTYPE", "[CDATA["]),
+                    State::AfterDoctypeName => !is_cut_keyword(rest, &["PUBLIC", "SYSTEM"]),
+                    _ => true,
+                },
+            };
+        }
+        // The digits of a numeric character reference may go on in the next chunk.
+        let before_hex_digits = rest.trim_end_matches(|c: char| c.is_ascii_hexdigit());
+        let before_digits = rest.trim_end_matches(|c: char| c.is_ascii_digit());
+        let reference_start = if before_hex_digits.get(before_hex_digits.len().saturating_sub(3)..)
+                                                  .is_some_and(|end| end.eq_ignore_ascii_case("&#x")) {
+            Some(before_hex_digits.len() - 3)
+        } else if before_digits.ends_with("&#") {
+            Some(before_digits.len() - 2)
+        } else {
+            None
+        };
+        reference_start.is_none_or(|start| start >= LOOKAHEAD)
+    }
+
+    // Tokenizes what follows as the text content of the last start tag.
+    pub fn start_text(&mut self, kind: TextKind) {
+        self.state = match kind {
+            TextKind::Rcdata => State::Text(TextState::Rcdata),
+            TextKind::Rawtext => State::Text(TextState::Rawtext),
+            TextKind::ScriptData => State::Text(TextState::ScriptData),
+            TextKind::Plaintext => State::Plaintext,
+        };
+    }
+
+    pub fn set_cdata_allowed(&mut self, allowed: bool) {
+        self.cdata_allowed = allowed;
+    }
+
+    // Part of the source the last returned token comes from.
+    pub fn span(&self) -> Span {
+        self.last_span
+    }
+
+    // Location of the end of the source.
+    pub fn end(&self) -> Location {
+        self.location(self.drained + self.input.len())
+    }
+
+    // Location of an offset in the whole document.
+    fn location(&self, offset: usize) -> Location {
+        self.lines.location(&self.input, offset)
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn error(&mut self, message: &str) {
+        let location = self.location(self.drained + self.pos);
+        self.errors.push(ParseError{location, message: message.to_string()});
+    }
+
+    fn consume(&mut self) -> Option<char> {
+        let c = self.input[self.pos..].chars().next()?;
+        self.last_len = c.len_utf8();
+        self.pos += c.len_utf8();
+        // Newlines are normalized to '\n'.
+        if c == '\r' {
+            if self.input[self.pos..].starts_with('\n') {
+                self.pos += 1;
+                self.last_len += 1;
+            }
+            return Some('\n');
+        }
+        Some(c)
+    }
+
+    fn reconsume(&mut self, c: Option<char>) {
+        if c.is_some() {
+            self.pos -= self.last_len;
+        }
+    }
+
+    // Consumes `expected` if the input continues with it.
+    fn consume_if_matches(&mut self, expected: &str, ignore_case: bool) -> bool {
+        let rest = &self.input[self.pos..];
+        let matches = match rest.get(..expected.len()) {
+            Some(next) if ignore_case => next.eq_ignore_ascii_case(expected),
+            Some(next) => next == expected,
+            None => false,
+        };
+        if matches {
+            self.pos += expected.len();
+        }
+        matches
+    }
+
+    fn emit(&mut self, token: Token, offset: usize) {
+        self.pending.push_back((token, self.drained + offset, self.drained + self.pos.max(offset)));
+    }
+
+    fn emit_char(&mut self, c: char) {
+        let offset = self.pos - self.last_len;
+        self.emit(Token::Character(c), offset);
+    }
+
+    fn emit_str(&mut self, s: &str, offset: usize) {
+        for c in s.chars() {
+            self.emit(Token::Character(c), offset);
+        }
+    }
+
+    fn start_tag(&mut self, is_end: bool) {
+        self.current_tag = Tag::default();
+        self.current_tag_is_end = is_end;
+    }
+
+    fn start_attribute(&mut self) {
+        self.finish_attribute();
+        self.current_attribute = Some(Attribute::default());
+    }
+
+    fn finish_attribute(&mut self) {
+        if let Some(attribute) = self.current_attribute.take() {
+            // Only the first of several attributes with the same name is kept.
+            if self.current_tag.attributes.iter().any(|attr| attr.name == attribute.name) {
+                self.error(&format!("duplicate attribute {}", attribute.name));
+            } else {
+                self.current_tag.attributes.push(attribute);
+            }
+        }
+    }
+
+    fn attribute(&mut self) -> &mut Attribute {
+        self.current_attribute.get_or_insert_with(Attribute::default)
+    }
+
+    fn emit_tag(&mut self) {
+        self.finish_attribute();
+        let tag = std::mem::take(&mut self.current_tag);
+        if self.current_tag_is_end {
+            if !tag.attributes.is_empty() {
+                self.error("end tag with attributes");
+            }
+            if tag.self_closing {
+                self.error("end tag with trailing solidus");
+            }
+            self.emit(Token::EndTag(tag), self.token_start);
+        } else {
+            self.last_start_tag_name = tag.name.clone();
+            self.emit(Token::StartTag(tag), self.token_start);
+        }
+    }
+
+    fn is_appropriate_end_tag(&self) -> bool {
+        self.current_tag_is_end && self.current_tag.name == self.last_start_tag_name
+    }
+
+    // Emits the characters of an end tag which turned out not to be one.
+    fn emit_text_end_tag_open(&mut self) {
+        let text = format!("</{}", self.temporary_buffer);
+        self.emit_str(&text, self.token_start);
+    }
+
+    fn emit_null_in_text(&mut self) {
+        self.error("unexpected null character");
+        self.emit_char('\u{fffd}');
+    }
+
+    fn emit_comment(&mut self) {
+        let comment = std::mem::take(&mut self.current_comment);
+        self.emit(Token::Comment(comment), self.token_start);
+    }
+
+    fn emit_doctype(&mut self) {
+        let doctype = std::mem::take(&mut self.current_doctype);
+        self.emit(Token::Doctype(doctype), self.token_start);
+    }
+
+    // Called after a '&' was consumed. Returns the referenced characters, or
+    // None (without consuming anything) if this is not a character reference.
+    fn consume_character_reference(&mut self, in_attribute: bool) -> Option<String> {
+        let rest = &self.input[self.pos..];
+        if rest.starts_with('#') {
+            return self.consume_numeric_character_reference();
+        }
+        let (name, value) = match match_named_character_reference(rest) {
+            Some(found) => found,
+            None => {
+                let name_len = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+                if name_len > 0 && rest[name_len..].starts_with(';') {
+                    self.error("unknown named character reference");
+                }
+                return None;
+            },
+        };
+        if !name.ends_with(';') {
+            // For historical reasons, "&copy=" or "&copyx" are kept as is in attributes.
+            let next = rest[name.len()..].chars().next();
+            if in_attribute && next.is_some_and(|c| c == '=' || c.is_ascii_alphanumeric()) {
+                return None;
+            }
+            self.error("missing semicolon after character reference");
+        }
+        self.pos += name.len();
+        Some(value.to_string())
+    }
+
+    fn consume_numeric_character_reference(&mut self) -> Option<String> {
+        let start = self.pos;
+        // Skip the '#'
+        self.pos += 1;
+        let radix = if self.consume_if_matches("x", true) { 16 } else { 10 };
+        let digits_len = self.input[self.pos..].find(|c: char| !c.is_digit(radix))
+                                               .unwrap_or(self.input.len() - self.pos);
+        if digits_len == 0 {
+            self.pos = start;
+            self.error("absence of digits in numeric character reference");
+            return None;
+        }
+        let digits = &self.input[self.pos..self.pos + digits_len];
+        let code = u32::from_str_radix(digits, radix).unwrap_or(u32::MAX);
+        self.pos += digits_len;
+        if !self.consume_if_matches(";", false) {
+            self.error("missing semicolon after character reference");
+        }
+        let c = match code {
+            0 => {
+                self.error("null character reference");
+                '\u{fffd}'
+            },
+            0xd800..=0xdfff => {
+                self.error("surrogate character reference");
+                '\u{fffd}'
+            },
+            _ if code > 0x10ffff => {
+                self.error("character reference outside unicode range");
+                '\u{fffd}'
+            },
+            _ => {
+                if is_noncharacter(code) {
+                    self.error("noncharacter character reference");
+                }
+                let is_control = code == 0x0d
+                    || (code < 0x20 && ![0x09, 0x0a, 0x0c].contains(&code))
+                    || (0x7f..=0x9f).contains(&code);
+                if is_control {
+                    self.error("control character reference");
+                }
+                C1_REPLACEMENTS.iter()
+                               .find(|(from, _)| *from == code)
+                               .map(|(_, to)| *to)
+                               .unwrap_or_else(|| char::from_u32(code).unwrap())
+            },
+        };
+        Some(c.to_string())
+    }
+
+    fn flush_attribute_character_reference(&mut self) {
+        match self.consume_character_reference(true) {
+            Some(value) => self.attribute().value.push_str(&value),
+            None => self.attribute().value.push('&'),
+        }
+    }
+
+    fn step(&mut self) {
+        let c = self.consume();
+        match self.state {
+            State::Text(text_state) => match c {
+                Some('&') if text_state == TextState::Rcdata => {
+                    let offset = self.pos - 1;
+                    match self.consume_character_reference(false) {
+                        Some(value) => self.emit_str(&value, offset),
+                        None => self.emit_char('&'),
+                    }
+                },
+                Some('<') => {
+                    self.token_start = self.pos - 1;
+                    self.state = State::TextLessThanSign(text_state);
+                },
+                Some('-') if text_state == TextState::ScriptDataEscaped => {
+                    self.state = State::ScriptDataEscapedDash;
+                    self.emit_char('-');
+                },
+                Some('\0') => self.emit_null_in_text(),
+                Some(c) => self.emit_char(c),
+                None => {
+                    if text_state == TextState::ScriptDataEscaped {
+                        self.error("eof in script html comment like text");
+                    }
+                    self.finished = true;
+                },
+            },
+            State::Plaintext => match c {
+                Some('\0') => self.emit_null_in_text(),
+                Some(c) => self.emit_char(c),
+                None => self.finished = true,
+            },
+            State::TextLessThanSign(text_state) => match c {
+                Some('/') => {
+                    self.temporary_buffer.clear();
+                    self.state = State::TextEndTagOpen(text_state);
+                },
+                Some('!') if text_state == TextState::ScriptData => {
+                    self.state = State::ScriptDataEscapeStart;
+                    self.emit_str("<!", self.token_start);
+                },
+                Some(c) if c.is_ascii_alphabetic() && text_state == TextState::ScriptDataEscaped => {
+                    self.temporary_buffer.clear();
+                    self.emit(Token::Character('<'), self.token_start);
+                    self.reconsume(Some(c));
+                    self.state = State::ScriptDataDoubleEscapeStart;
+                },
+                _ => {
+                    self.emit(Token::Character('<'), self.token_start);
+                    self.reconsume(c);
+                    self.state = State::Text(text_state);
+                },
+            },
+            State::TextEndTagOpen(text_state) => match c {
+                Some(c) if c.is_ascii_alphabetic() => {
+                    self.start_tag(true);
+                    self.reconsume(Some(c));
+                    self.state = State::TextEndTagName(text_state);
+                },
+                _ => {
+                    self.emit_str("</", self.token_start);
+                    self.reconsume(c);
+                    self.state = State::Text(text_state);
+                },
+            },
+            State::TextEndTagName(text_state) => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') if self.is_appropriate_end_tag() => {
+                    self.state = State::BeforeAttributeName;
+                },
+                Some('/') if self.is_appropriate_end_tag() => self.state = State::SelfClosingStartTag,
+                Some('>') if self.is_appropriate_end_tag() => {
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                Some(c) if c.is_ascii_alphabetic() => {
+                    self.current_tag.name.push(c.to_ascii_lowercase());
+                    self.temporary_buffer.push(c);
+                },
+                _ => {
+                    self.emit_text_end_tag_open();
+                    self.reconsume(c);
+                    self.state = State::Text(text_state);
+                },
+            },
+            State::ScriptDataEscapeStart | State::ScriptDataEscapeStartDash => match c {
+                Some('-') => {
+                    self.state = if self.state == State::ScriptDataEscapeStart {
+                        State::ScriptDataEscapeStartDash
+                    } else {
+                        State::ScriptDataEscapedDashDash
+                    };
+                    self.emit_char('-');
+                },
+                _ => {
+                    self.reconsume(c);
+                    self.state = State::Text(TextState::ScriptData);
+                },
+            },
+            State::ScriptDataEscapedDash | State::ScriptDataEscapedDashDash => match c {
+                Some('-') => {
+                    self.state = State::ScriptDataEscapedDashDash;
+                    self.emit_char('-');
+                },
+                Some('<') => {
+                    self.token_start = self.pos - 1;
+                    self.state = State::TextLessThanSign(TextState::ScriptDataEscaped);
+                },
+                Some('>') if self.state == State::ScriptDataEscapedDashDash => {
+                    self.state = State::Text(TextState::ScriptData);
+                    self.emit_char('>');
+                },
+                Some('\0') => {
+                    self.state = State::Text(TextState::ScriptDataEscaped);
+                    self.emit_null_in_text();
+                },
+                Some(c) => {
+                    self.state = State::Text(TextState::ScriptDataEscaped);
+                    self.emit_char(c);
+                },
+                None => {
+                    self.error("eof in script html comment like text");
+                    self.finished = true;
+                },
+            },
+            State::ScriptDataDoubleEscapeStart | State::ScriptDataDoubleEscapeEnd => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') | Some('/') | Some('>') => {
+                    let entering = self.state == State::ScriptDataDoubleEscapeStart;
+                    self.state = match (self.temporary_buffer == "script", entering) {
+                        (true, true) | (false, false) => State::ScriptDataDoubleEscaped,
+                        (true, false) | (false, true) => State::Text(TextState::ScriptDataEscaped),
+                    };
+                    self.emit_char(c.unwrap());
+                },
+                Some(c) if c.is_ascii_alphabetic() => {
+                    self.temporary_buffer.push(c.to_ascii_lowercase());
+                    self.emit_char(c);
+                },
+                _ => {
+                    self.reconsume(c);
+                    self.state = if self.state == State::ScriptDataDoubleEscapeStart {
+                        State::Text(TextState::ScriptDataEscaped)
+                    } else {
+                        State::ScriptDataDoubleEscaped
+                    };
+                },
+            },
+            State::ScriptDataDoubleEscaped | State::ScriptDataDoubleEscapedDash
+            | State::ScriptDataDoubleEscapedDashDash => match c {
+                Some('-') => {
+                    if self.state != State::ScriptDataDoubleEscaped {
+                        self.state = State::ScriptDataDoubleEscapedDashDash;
+                    } else {
+                        self.state = State::ScriptDataDoubleEscapedDash;
+                    }
+                    self.emit_char('-');
+                },
+                Some('<') => {
+                    self.state = State::ScriptDataDoubleEscapedLessThanSign;
+                    self.emit_char('<');
+                },
+                Some('>') if self.state == State::ScriptDataDoubleEscapedDashDash => {
+                    self.state = State::Text(TextState::ScriptData);
+                    self.emit_char('>');
+                },
+                Some('\0') => {
+                    self.state = State::ScriptDataDoubleEscaped;
+                    self.emit_null_in_text();
+                },
+                Some(c) => {
+                    self.state = State::ScriptDataDoubleEscaped;
+                    self.emit_char(c);
+                },
+                None => {
+                    self.error("eof in script html comment like text");
+                    self.finished = true;
+                },
+            },
+            State::ScriptDataDoubleEscapedLessThanSign => match c {
+                Some('/') => {
+                    self.temporary_buffer.clear();
+                    self.state = State::ScriptDataDoubleEscapeEnd;
+                    self.emit_char('/');
+                },
+                _ => {
+                    self.reconsume(c);
+                    self.state = State::ScriptDataDoubleEscaped;
+                },
+            },
+            State::Data => match c {
+                Some('&') => {
+                    let offset = self.pos - 1;
+                    match self.consume_character_reference(false) {
+                        Some(value) => self.emit_str(&value, offset),
+                        None => self.emit_char('&'),
+                    }
+                },
+                Some('<') => {
+                    self.token_start = self.pos - 1;
+                    self.state = State::TagOpen;
+                },
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.emit_char('\0');
+                },
+                Some(c) => self.emit_char(c),
+                None => self.finished = true,
+            },
+            State::TagOpen => match c {
+                Some('!') => self.state = State::MarkupDeclarationOpen,
+                Some('/') => self.state = State::EndTagOpen,
+                Some(c) if c.is_ascii_alphabetic() => {
+                    self.start_tag(false);
+                    self.reconsume(Some(c));
+                    self.state = State::TagName;
+                },
+                Some('?') => {
+                    self.error("unexpected question mark instead of tag name");
+                    self.current_comment.clear();
+                    self.reconsume(c);
+                    self.state = State::BogusComment;
+                },
+                None => {
+                    self.error("eof before tag name");
+                    self.emit(Token::Character('<'), self.token_start);
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("invalid first character of tag name");
+                    self.emit(Token::Character('<'), self.token_start);
+                    self.reconsume(c);
+                    self.state = State::Data;
+                },
+            },
+            State::EndTagOpen => match c {
+                Some(c) if c.is_ascii_alphabetic() => {
+                    self.start_tag(true);
+                    self.reconsume(Some(c));
+                    self.state = State::TagName;
+                },
+                Some('>') => {
+                    self.error("missing end tag name");
+                    self.state = State::Data;
+                },
+                None => {
+                    self.error("eof before tag name");
+                    self.emit_str("</", self.token_start);
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("invalid first character of tag name");
+                    self.current_comment.clear();
+                    self.reconsume(c);
+                    self.state = State::BogusComment;
+                },
+            },
+            State::TagName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::BeforeAttributeName,
+                Some('/') => self.state = State::SelfClosingStartTag,
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.current_tag.name.push('\u{fffd}');
+                },
+                Some(c) => self.current_tag.name.push(c.to_ascii_lowercase()),
+                None => {
+                    self.error("eof in tag");
+                    self.finished = true;
+                },
+            },
+            State::BeforeAttributeName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {},
+                Some('/') | Some('>') | None => {
+                    self.reconsume(c);
+                    self.state = State::AfterAttributeName;
+                },
+                Some('=') => {
+                    self.error("unexpected equals sign before attribute name");
+                    self.start_attribute();
+                    self.attribute().name.push('=');
+                    self.state = State::AttributeName;
+                },
+                Some(_) => {
+                    self.start_attribute();
+                    self.reconsume(c);
+                    self.state = State::AttributeName;
+                },
+            },
+            State::AttributeName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') | Some('/') | Some('>') | None => {
+                    self.reconsume(c);
+                    self.state = State::AfterAttributeName;
+                },
+                Some('=') => self.state = State::BeforeAttributeValue,
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.attribute().name.push('\u{fffd}');
+                },
+                Some(c) => {
+                    if c == '"' || c == '\'' || c == '<' {
+                        self.error("unexpected character in attribute name");
+                    }
+                    self.attribute().name.push(c.to_ascii_lowercase());
+                },
+            },
+            State::AfterAttributeName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {},
+                Some('/') => self.state = State::SelfClosingStartTag,
+                Some('=') => self.state = State::BeforeAttributeValue,
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                None => {
+                    self.error("eof in tag");
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.start_attribute();
+                    self.reconsume(c);
+                    self.state = State::AttributeName;
+                },
+            },
+            State::BeforeAttributeValue => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {},
+                Some('"') | Some('\'') => self.state = State::AttributeValueQuoted(c.unwrap()),
+                Some('>') => {
+                    self.error("missing attribute value");
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                _ => {
+                    self.reconsume(c);
+                    self.state = State::AttributeValueUnquoted;
+                },
+            },
+            State::AttributeValueQuoted(quote) => match c {
+                Some(c) if c == quote => self.state = State::AfterAttributeValueQuoted,
+                Some('&') => self.flush_attribute_character_reference(),
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.attribute().value.push('\u{fffd}');
+                },
+                Some(c) => self.attribute().value.push(c),
+                None => {
+                    self.error("eof in tag");
+                    self.finished = true;
+                },
+            },
+            State::AttributeValueUnquoted => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::BeforeAttributeName,
+                Some('&') => self.flush_attribute_character_reference(),
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.attribute().value.push('\u{fffd}');
+                },
+                Some(c) => {
+                    if c == '"' || c == '\'' || c == '<' || c == '=' || c == '`' {
+                        self.error("unexpected character in unquoted attribute value");
+                    }
+                    self.attribute().value.push(c);
+                },
+                None => {
+                    self.error("eof in tag");
+                    self.finished = true;
+                },
+            },
+            State::AfterAttributeValueQuoted => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::BeforeAttributeName,
+                Some('/') => self.state = State::SelfClosingStartTag,
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                None => {
+                    self.error("eof in tag");
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("missing whitespace between attributes");
+                    self.reconsume(c);
+                    self.state = State::BeforeAttributeName;
+                },
+            },
+            State::SelfClosingStartTag => match c {
+                Some('>') => {
+                    self.current_tag.self_closing = true;
+                    self.state = State::Data;
+                    self.emit_tag();
+                },
+                None => {
+                    self.error("eof in tag");
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("unexpected solidus in tag");
+                    self.reconsume(c);
+                    self.state = State::BeforeAttributeName;
+                },
+            },
+            State::BogusComment => match c {
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_comment();
+                },
+                None => {
+                    self.emit_comment();
+                    self.finished = true;
+                },
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.current_comment.push('\u{fffd}');
+                },
+                Some(c) => self.current_comment.push(c),
+            },
+            State::MarkupDeclarationOpen => {
+                self.reconsume(c);
+                if self.consume_if_matches("--", false) {
+                    self.current_comment.clear();
+                    self.state = State::CommentStart;
+                } else if self.consume_if_matches("DOCTYPE", true) {
+                    self.state = State::Doctype;
+                } else if self.consume_if_matches("[CDATA[", false) {
+                    if self.cdata_allowed {
+                        self.state = State::CdataSection;
+                    } else {
+                        self.error("cdata in html content");
+                        self.current_comment = "[CDATA[".to_string();
+                        self.state = State::BogusComment;
+                    }
+                } else {
+                    self.error("incorrectly opened comment");
+                    self.current_comment.clear();
+                    self.state = State::BogusComment;
+                }
+            },
+            State::CommentStart => match c {
+                Some('-') => self.state = State::CommentStartDash,
+                Some('>') => {
+                    self.error("abrupt closing of empty comment");
+                    self.state = State::Data;
+                    self.emit_comment();
+                },
+                _ => {
+                    self.reconsume(c);
+                    self.state = State::Comment;
+                },
+            },
+            State::CommentStartDash => match c {
+                Some('-') => self.state = State::CommentEnd,
+                Some('>') => {
+                    self.error("abrupt closing of empty comment");
+                    self.state = State::Data;
+                    self.emit_comment();
+                },
+                None => {
+                    self.error("eof in comment");
+                    self.emit_comment();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.current_comment.push('-');
+                    self.reconsume(c);
+                    self.state = State::Comment;
+                },
+            },
+            State::Comment => match c {
+                Some('-') => self.state = State::CommentEndDash,
+                Some('\0') => {
+                    self.error("unexpected null character");
+                    self.current_comment.push('\u{fffd}');
+                },
+                Some(c) => self.current_comment.push(c),
+                None => {
+                    self.error("eof in comment");
+                    self.emit_comment();
+                    self.finished = true;
+                },
+            },
+            State::CommentEndDash => match c {
+                Some('-') => self.state = State::CommentEnd,
+                None => {
+                    self.error("eof in comment");
+                    self.emit_comment();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.current_comment.push('-');
+                    self.reconsume(c);
+                    self.state = State::Comment;
+                },
+            },
+            State::CommentEnd => match c {
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_comment();
+                },
+                Some('!') => self.state = State::CommentEndBang,
+                Some('-') => self.current_comment.push('-'),
+                None => {
+                    self.error("eof in comment");
+                    self.emit_comment();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.current_comment.push_str("--");
+                    self.reconsume(c);
+                    self.state = State::Comment;
+                },
+            },
+            State::CommentEndBang => match c {
+                Some('-') => {
+                    self.current_comment.push_str("--!");
+                    self.state = State::CommentEndDash;
+                },
+                Some('>') => {
+                    self.error("incorrectly closed comment");
+                    self.state = State::Data;
+                    self.emit_comment();
+                },
+                None => {
+                    self.error("eof in comment");
+                    self.emit_comment();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.current_comment.push_str("--!");
+                    self.reconsume(c);
+                    self.state = State::Comment;
+                },
+            },
+            State::Doctype => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::BeforeDoctypeName,
+                Some('>') => {
+                    self.reconsume(c);
+                    self.state = State::BeforeDoctypeName;
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype = Doctype { force_quirks: true, ..Doctype::default() };
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("missing whitespace before doctype name");
+                    self.reconsume(c);
+                    self.state = State::BeforeDoctypeName;
+                },
+            },
+            State::BeforeDoctypeName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {},
+                Some('>') => {
+                    self.error("missing doctype name");
+                    self.current_doctype = Doctype { force_quirks: true, ..Doctype::default() };
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype = Doctype { force_quirks: true, ..Doctype::default() };
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(c) => {
+                    let c = if c == '\0' { '\u{fffd}' } else { c.to_ascii_lowercase() };
+                    self.current_doctype = Doctype { name: Some(c.to_string()), ..Doctype::default() };
+                    self.state = State::DoctypeName;
+                },
+            },
+            State::DoctypeName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::AfterDoctypeName,
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(c) => {
+                    let c = if c == '\0' { '\u{fffd}' } else { c.to_ascii_lowercase() };
+                    self.current_doctype.name.get_or_insert_with(String::new).push(c);
+                },
+            },
+            State::AfterDoctypeName => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {},
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.reconsume(c);
+                    if self.consume_if_matches("PUBLIC", true) {
+                        self.state = State::AfterDoctypePublicKeyword;
+                    } else if self.consume_if_matches("SYSTEM", true) {
+                        self.state = State::AfterDoctypeSystemKeyword;
+                    } else {
+                        self.error("invalid character sequence after doctype name");
+                        self.current_doctype.force_quirks = true;
+                        self.state = State::BogusDoctype;
+                    }
+                },
+            },
+            State::AfterDoctypePublicKeyword | State::BeforeDoctypePublicIdentifier => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::BeforeDoctypePublicIdentifier,
+                Some('"') | Some('\'') => {
+                    if self.state == State::AfterDoctypePublicKeyword {
+                        self.error("missing whitespace after doctype public keyword");
+                    }
+                    self.current_doctype.public_id = Some(String::new());
+                    self.state = State::DoctypePublicIdentifierQuoted(c.unwrap());
+                },
+                Some('>') => {
+                    self.error("missing doctype public identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("missing quote before doctype public identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.reconsume(c);
+                    self.state = State::BogusDoctype;
+                },
+            },
+            State::DoctypePublicIdentifierQuoted(quote) => match c {
+                Some(c) if c == quote => self.state = State::AfterDoctypePublicIdentifier,
+                Some('>') => {
+                    self.error("abrupt doctype public identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(c) => {
+                    let c = if c == '\0' { '\u{fffd}' } else { c };
+                    self.current_doctype.public_id.get_or_insert_with(String::new).push(c);
+                },
+            },
+            State::AfterDoctypePublicIdentifier | State::BetweenDoctypePublicAndSystemIdentifiers => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {
+                    self.state = State::BetweenDoctypePublicAndSystemIdentifiers;
+                },
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                Some('"') | Some('\'') => {
+                    if self.state == State::AfterDoctypePublicIdentifier {
+                        self.error("missing whitespace between doctype public and system identifiers");
+                    }
+                    self.current_doctype.system_id = Some(String::new());
+                    self.state = State::DoctypeSystemIdentifierQuoted(c.unwrap());
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("missing quote before doctype system identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.reconsume(c);
+                    self.state = State::BogusDoctype;
+                },
+            },
+            State::AfterDoctypeSystemKeyword | State::BeforeDoctypeSystemIdentifier => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => self.state = State::BeforeDoctypeSystemIdentifier,
+                Some('"') | Some('\'') => {
+                    if self.state == State::AfterDoctypeSystemKeyword {
+                        self.error("missing whitespace after doctype system keyword");
+                    }
+                    self.current_doctype.system_id = Some(String::new());
+                    self.state = State::DoctypeSystemIdentifierQuoted(c.unwrap());
+                },
+                Some('>') => {
+                    self.error("missing doctype system identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    self.error("missing quote before doctype system identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.reconsume(c);
+                    self.state = State::BogusDoctype;
+                },
+            },
+            State::DoctypeSystemIdentifierQuoted(quote) => match c {
+                Some(c) if c == quote => self.state = State::AfterDoctypeSystemIdentifier,
+                Some('>') => {
+                    self.error("abrupt doctype system identifier");
+                    self.current_doctype.force_quirks = true;
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(c) => {
+                    let c = if c == '\0' { '\u{fffd}' } else { c };
+                    self.current_doctype.system_id.get_or_insert_with(String::new).push(c);
+                },
+            },
+            State::AfterDoctypeSystemIdentifier => match c {
+                Some('\t') | Some('\n') | Some('\x0C') | Some(' ') => {},
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.error("eof in doctype");
+                    self.current_doctype.force_quirks = true;
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {
+                    // Unlike other doctype errors, this one does not set force-quirks.
+                    self.error("unexpected character after doctype system identifier");
+                    self.reconsume(c);
+                    self.state = State::BogusDoctype;
+                },
+            },
+            State::BogusDoctype => match c {
+                Some('>') => {
+                    self.state = State::Data;
+                    self.emit_doctype();
+                },
+                None => {
+                    self.emit_doctype();
+                    self.finished = true;
+                },
+                Some(_) => {},
+            },
+            State::CdataSection => match c {
+                Some(']') => self.state = State::CdataSectionBracket,
+                Some(c) => self.emit_char(c),
+                None => {
+                    self.error("eof in cdata");
+                    self.finished = true;
+                },
+            },
+            State::CdataSectionBracket => match c {
+                Some(']') => self.state = State::CdataSectionEnd,
+                _ => {
+                    self.reconsume(c);
+                    self.emit(Token::Character(']'), self.pos - 1);
+                    self.state = State::CdataSection;
+                },
+            },
+            State::CdataSectionEnd => match c {
+                Some(']') => self.emit_char(']'),
+                Some('>') => self.state = State::Data,
+                _ => {
+                    self.reconsume(c);
+                    self.emit_str("]]", self.pos - 2);
+                    self.state = State::CdataSection;
+                },
+            },
+        }
+    }
+}
+
+// Returns None once all the tokens were returned, or when an incremental
+// tokenizer needs more input to go on.
+impl Iterator for Tokenizer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        while self.pending.is_empty() && !self.finished && self.can_step() {
+            self.step();
+        }
+        let (token, start, end) = self.pending.pop_front()?;
+        self.last_span = Span { start: self.location(start), end: self.location(end) };
+        Some(token)
+    }
+}
+
+// Tokenizes a whole document without building a tree, e.g. to lint markup.
+pub fn tokenize(source: &str) -> (Vec<Token>, Vec<ParseError>) {
+    let mut tokenizer = Tokenizer::new(source);
+    let mut tokens = Vec::new();
+    while let Some(token) = tokenizer.next() {
+        if let Token::StartTag(tag) = &token {
+            if let Some(kind) = TextKind::of_element(&tag.name) {
+                tokenizer.start_text(kind);
+            }
+        }
+        tokens.push(token);
+    }
+    (tokens, tokenizer.take_errors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, ClosedError, Token, Tokenizer, TextKind};
+    use crate::html::ParseError;
+
+    // Writes the tokens in a compact form, with the characters merged into
+    // strings: `"text"`, `<p a="b">`, `</p>`, `<!--c-->`, `<!DOCTYPE html>`.
+    fn describe(tokens: &[Token]) -> Vec<String> {
+        let mut described = Vec::new();
+        let mut text = String::new();
+        for token in tokens {
+            if let Token::Character(c) = token {
+                text.push(*c);
+                continue;
+            }
+            if !text.is_empty() {
+                described.push(format!("{:?}", std::mem::take(&mut text)));
+            }
+            described.push(match token {
+                Token::StartTag(tag) => {
+                    let attributes: String = tag.attributes.iter()
+                                                           .map(|attribute| format!(" {}={:?}", attribute.name, attribute.value))
+                                                           .collect();
+                    let solidus = if tag.self_closing { "/" } else { "" };
+                    format!("<{}{}{}>", tag.name, attributes, solidus)
+                },
+                Token::EndTag(tag) => format!("</{}>", tag.name),
+                Token::Comment(comment) => format!("<!--{}-->", comment),
+                Token::Doctype(doctype) => {
+                    let mut out = format!("<!DOCTYPE {}", doctype.name.as_deref().unwrap_or(""));
+                    if let Some(public_id) = &doctype.public_id {
+                        out.push_str(&format!(" PUBLIC {:?}", public_id));
+                    }
+                    if let Some(system_id) = &doctype.system_id {
+                        out.push_str(&format!(" SYSTEM {:?}", system_id));
+                    }
+                    if doctype.force_quirks {
+                        out.push_str(" quirks");
+                    }
+                    out.push('>');
+                    out
+                },
+                Token::Character(_) => unreachable!(),
+            });
+        }
+        if !text.is_empty() {
+            described.push(format!("{:?}", text));
+        }
+        described
+    }
+
+    fn assert_tokens(source: &str, expected: &[&str]) {
+        let (tokens, _) = tokenize(source);
+        assert_eq!(describe(&tokens), expected, "tokens of {:?}", source);
+    }
+
+    fn error_messages(source: &str) -> Vec<String> {
+        tokenize(source).1.into_iter().map(|error| error.message).collect()
+    }
+
+    // Tokenizes the chunks one after the other with an incremental tokenizer,
+    // switching to text content like `tokenize`.
+    fn tokenize_chunks(chunks: &[&str]) -> (Vec<Token>, Vec<ParseError>) {
+        let mut tokenizer = Tokenizer::incremental();
+        let mut tokens = Vec::new();
+        let mut take_tokens = |tokenizer: &mut Tokenizer| {
+            while let Some(token) = tokenizer.next() {
+                if let Token::StartTag(tag) = &token {
+                    if let Some(kind) = TextKind::of_element(&tag.name) {
+                        tokenizer.start_text(kind);
+                    }
+                }
+                tokens.push(token);
+            }
+        };
+        for chunk in chunks {
+            tokenizer.feed(chunk).unwrap();
+            take_tokens(&mut tokenizer);
+        }
+        tokenizer.close();
+        take_tokens(&mut tokenizer);
+        assert!(tokenizer.is_finished());
+        (tokens, tokenizer.take_errors())
+    }
+
+    #[test]
+    fn tags_and_attributes() {
+        assert_tokens("<p class=a id='b' title=\"c\" hidden>x</P>",
+                      &["<p class=\"a\" id=\"b\" title=\"c\" hidden=\"\">", "\"x\"", "</p>"]);
+        assert_tokens("<br/><img src=a.png />", &["<br/>", "<img src=\"a.png\"/>"]);
+        // Only the first of duplicate attributes is kept.
+        assert_tokens("<p a=1 A=2>", &["<p a=\"1\">"]);
+        assert_tokens("a <b < c>", &["\"a \"", "<b <=\"\" c=\"\">"]);
+        assert_tokens("a < b <1", &["\"a < b <1\""]);
+        assert_tokens("</>a</ b>", &["\"a\"", "<!-- b-->"]);
+    }
+
+    #[test]
+    fn comments_and_doctypes() {
+        assert_tokens("<!--a-- b--><!----><!-->x", &["<!--a-- b-->", "<!---->", "<!---->", "\"x\""]);
+        assert_tokens("<?xml version=1?>", &["<!--?xml version=1?-->"]);
+        assert_tokens("<!doctype HTML>", &["<!DOCTYPE html>"]);
+        assert_tokens("<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\" 'http://www.w3.org/TR/html4/strict.dtd'>",
+                      &["<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\" SYSTEM \"http://www.w3.org/TR/html4/strict.dtd\">"]);
+        assert_tokens("<!DOCTYPE>", &["<!DOCTYPE  quirks>"]);
+        assert_tokens("<!DOCTYPE html bogus>", &["<!DOCTYPE html quirks>"]);
+    }
+
+    #[test]
+    fn character_references() {
+        assert_tokens("&amp;&lt;&AMP &notin; &notit; &#65;&#x42;&#X43;", &["\"&<& ∉ ¬it; ABC\""]);
+        // Numeric references to C1 controls mean Windows-1252 characters.
+        assert_tokens("&#x80;&#150;&#x81;", &["\"€–\\u{81}\""]);
+        assert_tokens("&#0;&#xd800;&#x110000;&#99999999999;", &["\"\u{fffd}\u{fffd}\u{fffd}\u{fffd}\""]);
+        assert_tokens("&#;&#x;&unknown;&", &["\"&#;&#x;&unknown;&\""]);
+        assert_eq!(error_messages("&#65&copy&#x80;&#1;&unknown;&#;"), [
+            "missing semicolon after character reference",
+            "missing semicolon after character reference",
+            "control character reference",
+            "control character reference",
+            "unknown named character reference",
+            "absence of digits in numeric character reference",
+        ]);
+    }
+
+    #[test]
+    fn character_references_in_attributes() {
+        assert_tokens("<a title='&amp;&lt' href=\"?a=1&copy=2&copyx&copy;\" alt=&gt;x>",
+                      &["<a title=\"&<\" href=\"?a=1&copy=2&copyx©\" alt=\">x\">"]);
+        // References are not decoded in RAWTEXT and script data.
+        assert_tokens("<style>&amp;</style><title>&amp;</title>",
+                      &["<style>", "\"&amp;\"", "</style>", "<title>", "\"&\"", "</title>"]);
+    }
+
+    #[test]
+    fn text_content_ends_with_its_end_tag() {
+        assert_tokens("<textarea><p></textareax></TEXTAREA>",
+                      &["<textarea>", "\"<p></textareax>\"", "</textarea>"]);
+        assert_tokens("<style></p></style >", &["<style>", "\"</p>\"", "</style>"]);
+        assert_tokens("<plaintext></plaintext><p>", &["<plaintext>", "\"</plaintext><p>\""]);
+    }
+
+    #[test]
+    fn script_data_escape_states() {
+        // </script> ends the escaped text of "<!--"...
+        assert_tokens("<script><!-- a </script>b",
+                      &["<script>", "\"<!-- a \"", "</script>", "\"b\""]);
+        // ...but not the double escaped text of a "<script>" inside it.
+        assert_tokens("<script><!--<script></script>--></script>",
+                      &["<script>", "\"<!--<script></script>-->\"", "</script>"]);
+        assert_tokens("<script><!--<script>a<!--b-></script>c--></script>",
+                      &["<script>", "\"<!--<script>a<!--b-></script>c-->\"", "</script>"]);
+        // "-->" goes back to script data, where "<script>" is text.
+        assert_tokens("<script><!-- - -- --> <script></script>",
+                      &["<script>", "\"<!-- - -- --> <script>\"", "</script>"]);
+        assert_tokens("<script><!---->x</script>", &["<script>", "\"<!---->x\"", "</script>"]);
+        assert_tokens("<script><!-<script></script>", &["<script>", "\"<!-<script>\"", "</script>"]);
+        assert_tokens("<script>\0</script>", &["<script>", "\"\u{fffd}\"", "</script>"]);
+    }
+
+    #[test]
+    fn cdata_sections() {
+        assert_tokens("<![CDATA[a]]>", &["<!--[CDATA[a]]-->"]);
+        assert_eq!(error_messages("<![CDATA[a]]>"), ["cdata in html content"]);
+
+        let mut tokenizer = Tokenizer::new("<![CDATA[a<b>]]c]]]>d<![CDATA[e");
+        tokenizer.set_cdata_allowed(true);
+        let tokens: Vec<Token> = tokenizer.by_ref().collect();
+        assert_eq!(describe(&tokens), ["\"a<b>]]c]de\""]);
+        assert_eq!(tokenizer.errors().iter().map(|error| error.message.as_str()).collect::<Vec<_>>(), ["eof in cdata"]);
+    }
+
+    #[test]
+    fn end_of_file_in_each_state() {
+        let cases: &[(&str, &[&str], &[&str])] = &[
+            ("<", &["\"<\""], &["eof before tag name"]),
+            ("</", &["\"</\""], &["eof before tag name"]),
+            ("<p", &[], &["eof in tag"]),
+            ("<p ", &[], &["eof in tag"]),
+            ("<p a", &[], &["eof in tag"]),
+            ("<p a ", &[], &["eof in tag"]),
+            ("<p a=", &[], &["eof in tag"]),
+            ("<p a='b", &[], &["eof in tag"]),
+            ("<p a=b", &[], &["eof in tag"]),
+            ("<p a='b'", &[], &["eof in tag"]),
+            ("<p/", &[], &["eof in tag"]),
+            ("<!", &["<!---->"], &["incorrectly opened comment"]),
+            ("<!-", &["<!----->"], &["incorrectly opened comment"]),
+            ("<?a", &["<!--?a-->"], &["unexpected question mark instead of tag name"]),
+            ("<!--", &["<!---->"], &["eof in comment"]),
+            ("<!---", &["<!---->"], &["eof in comment"]),
+            ("<!--a", &["<!--a-->"], &["eof in comment"]),
+            ("<!--a-", &["<!--a-->"], &["eof in comment"]),
+            ("<!--a--", &["<!--a-->"], &["eof in comment"]),
+            ("<!--a--!", &["<!--a-->"], &["eof in comment"]),
+            ("<!DOCTYPE", &["<!DOCTYPE  quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE ", &["<!DOCTYPE  quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html", &["<!DOCTYPE html quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html ", &["<!DOCTYPE html quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html PUBLIC", &["<!DOCTYPE html quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html PUBLIC ", &["<!DOCTYPE html quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html PUBLIC 'a", &["<!DOCTYPE html PUBLIC \"a\" quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html PUBLIC 'a'", &["<!DOCTYPE html PUBLIC \"a\" quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html PUBLIC 'a' ", &["<!DOCTYPE html PUBLIC \"a\" quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html SYSTEM", &["<!DOCTYPE html quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html SYSTEM ", &["<!DOCTYPE html quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html SYSTEM 'b", &["<!DOCTYPE html SYSTEM \"b\" quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html SYSTEM 'b' ", &["<!DOCTYPE html SYSTEM \"b\" quirks>"], &["eof in doctype"]),
+            ("<!DOCTYPE html bogus", &["<!DOCTYPE html quirks>"], &["invalid character sequence after doctype name"]),
+            ("<title>a</tit", &["<title>", "\"a</tit\""], &[]),
+            ("<script><!--a", &["<script>", "\"<!--a\""], &["eof in script html comment like text"]),
+            ("<script><!--a-", &["<script>", "\"<!--a-\""], &["eof in script html comment like text"]),
+            ("<script><!--a--", &["<script>", "\"<!--a--\""], &["eof in script html comment like text"]),
+            ("<script><!--<script>", &["<script>", "\"<!--<script>\""], &["eof in script html comment like text"]),
+            ("<script><!--<script>-", &["<script>", "\"<!--<script>-\""], &["eof in script html comment like text"]),
+            ("<script><!--<script><", &["<script>", "\"<!--<script><\""], &["eof in script html comment like text"]),
+            ("<script><!--<script></scr", &["<script>", "\"<!--<script></scr\""], &["eof in script html comment like text"]),
+        ];
+        for (source, tokens, errors) in cases {
+            assert_tokens(source, tokens);
+            assert_eq!(&error_messages(source), errors, "errors of {:?}", source);
+        }
+    }
+
+    #[test]
+    fn chunks_are_tokenized_like_the_whole_input() {
+        let sources = [
+            "<!DOCTYPE html PUBLIC \"a\" 'b'><html lang=en><!-- c --><p class='x y' hidden>a&amp;b&notit;&#x20AC;&#128 c</p>",
+            "<title>&lt;a&gt;</title><textarea>\r\nb\rc</textarea><style>p > a {}</style>",
+            "<script><!--<script>a</script>b--></script><![CDATA[x]]><?y?></z a=1>",
+            "é日本語&eacute;&#233;<a title=\"&copy=&copyx\">&",
+        ];
+        for source in sources {
+            let whole = tokenize(source);
+            let boundaries: Vec<usize> = (0..=source.len()).filter(|&i| source.is_char_boundary(i)).collect();
+            for &i in &boundaries {
+                let (before, after) = source.split_at(i);
+                assert_eq!(tokenize_chunks(&[before, after]), whole, "split of {:?} at {}", source, i);
+            }
+            let chars: Vec<&str> = boundaries.windows(2).map(|range| &source[range[0]..range[1]]).collect();
+            assert_eq!(tokenize_chunks(&chars), whole, "{:?} fed one character at a time", source);
+        }
+    }
+
+    #[test]
+    fn feeding_a_closed_tokenizer_fails() {
+        let mut tokenizer = Tokenizer::incremental();
+        tokenizer.feed("<p>").unwrap();
+        assert!(tokenizer.next().is_some());
+        assert!(tokenizer.next().is_none());
+        assert!(!tokenizer.is_finished());
+        tokenizer.close();
+        assert_eq!(tokenizer.feed("a"), Err(ClosedError));
+        assert!(tokenizer.next().is_none());
+        assert!(tokenizer.is_finished());
+    }
+}
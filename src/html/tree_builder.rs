@@ -0,0 +1,2568 @@
+use crate::dom;
+use crate::dom::{AttrMap, DoctypeData, ElementData, Namespace, NodeId, NodeType, QuirksMode};
+use crate::html::{Doctype, ParseError, Tag, TextKind, Token};
+use crate::source::{Location, Span};
+
+// Tree construction stage of the WHATWG HTML specification:
+// https://html.spec.whatwg.org/multipage/parsing.html#tree-construction
+//
+// Scripting is considered enabled, so the "in head noscript" insertion mode is
+// never used: <noscript> is always parsed as raw text.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InsertionMode {
+    Initial,
+    BeforeHtml,
+    BeforeHead,
+    InHead,
+    AfterHead,
+    InBody,
+    Text,
+    InTable,
+    InTableText,
+    InCaption,
+    InColumnGroup,
+    InTableBody,
+    InRow,
+    InCell,
+    InSelect,
+    InSelectInTable,
+    InTemplate,
+    AfterBody,
+    InFrameset,
+    AfterFrameset,
+    AfterAfterBody,
+    AfterAfterFrameset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    Default,
+    ListItem,
+    Button,
+    Table,
+    Select,
+}
+
+enum FormattingEntry {
+    Marker,
+    // The token is kept to be able to recreate the element.
+    Element(NodeId, Tag),
+}
+
+const DOCUMENT: NodeId = dom::Tree::ROOT;
+
+const SPECIAL_ELEMENTS: &[&str] = &[
+    "address", "applet", "area", "article", "aside", "base", "basefont", "bgsound",
+    "blockquote", "body", "br", "button", "caption", "center", "col", "colgroup", "dd",
+    "details", "dir", "div", "dl", "dt", "embed", "fieldset", "figcaption", "figure",
+    "footer", "form", "frame", "frameset", "h1", "h2", "h3", "h4", "h5", "h6", "head",
+    "header", "hgroup", "hr", "html", "iframe", "img", "input", "keygen", "li", "link",
+    "listing", "main", "marquee", "menu", "meta", "nav", "noembed", "noframes",
+    "noscript", "object", "ol", "p", "param", "plaintext", "pre", "script", "search",
+    "section", "select", "source", "style", "summary", "table", "tbody", "td",
+    "template", "textarea", "tfoot", "th", "thead", "title", "tr", "track", "ul", "wbr",
+    "xmp",
+];
+
+const FORMATTING_ELEMENTS: &[&str] = &[
+    "a", "b", "big", "code", "em", "font", "i", "nobr", "s", "small", "strike", "strong",
+    "tt", "u",
+];
+
+// Start tags closing an open <p> element.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "center", "details", "dialog", "dir",
+    "div", "dl", "fieldset", "figcaption", "figure", "footer", "header", "hgroup", "main",
+    "menu", "nav", "ol", "p", "search", "section", "summary", "ul",
+];
+
+// End tags closing the matching element and everything opened inside it.
+const CLOSABLE_BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "button", "center", "details", "dialog",
+    "dir", "div", "dl", "fieldset", "figcaption", "figure", "footer", "header", "hgroup",
+    "listing", "main", "menu", "nav", "ol", "pre", "search", "section", "summary", "ul",
+];
+
+const HEADINGS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+
+const IMPLIED_END_TAGS: &[&str] = &[
+    "dd", "dt", "li", "optgroup", "option", "p", "rb", "rp", "rt", "rtc",
+];
+
+// Implied end tags closed when leaving a template.
+const THOROUGHLY_IMPLIED_END_TAGS: &[&str] = &[
+    "caption", "colgroup", "dd", "dt", "li", "optgroup", "option", "p", "rb", "rp", "rt",
+    "rtc", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+// Elements which may be left open at the end of the body without an error.
+const OPTIONAL_END_TAGS: &[&str] = &[
+    "dd", "dt", "li", "optgroup", "option", "p", "rb", "rp", "rt", "rtc", "tbody", "td",
+    "tfoot", "th", "thead", "tr", "body", "html",
+];
+
+const TABLE_SECTIONS: &[&str] = &["tbody", "tfoot", "thead"];
+
+// Elements which never have any content, and so no end tag.
+pub const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img",
+    "input", "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+// Start tags leaving SVG and MathML content, as they are unlikely to be meant
+// as foreign elements.
+const FOREIGN_BREAKOUT_ELEMENTS: &[&str] = &[
+    "b", "big", "blockquote", "body", "br", "center", "code", "dd", "div", "dl", "dt", "em",
+    "embed", "h1", "h2", "h3", "h4", "h5", "h6", "head", "hr", "i", "img", "li", "listing",
+    "menu", "meta", "nobr", "ol", "p", "pre", "ruby", "s", "small", "span", "strong",
+    "strike", "sub", "sup", "table", "tt", "u", "ul", "var",
+];
+
+// Case of the SVG tag and attribute names which are not lowercase, as the
+// tokenizer lowercases all names.
+const SVG_TAG_NAMES: &[&str] = &[
+    "altGlyph", "altGlyphDef", "altGlyphItem", "animateColor", "animateMotion",
+    "animateTransform", "clipPath", "feBlend", "feColorMatrix", "feComponentTransfer",
+    "feComposite", "feConvolveMatrix", "feDiffuseLighting", "feDisplacementMap",
+    "feDistantLight", "feDropShadow", "feFlood", "feFuncA", "feFuncB", "feFuncG", "feFuncR",
+    "feGaussianBlur", "feImage", "feMerge", "feMergeNode", "feMorphology", "feOffset",
+    "fePointLight", "feSpecularLighting", "feSpotLight", "feTile", "feTurbulence",
+    "foreignObject", "glyphRef", "linearGradient", "radialGradient", "textPath",
+];
+
+const SVG_ATTRIBUTE_NAMES: &[&str] = &[
+    "attributeName", "attributeType", "baseFrequency", "baseProfile", "calcMode",
+    "clipPathUnits", "diffuseConstant", "edgeMode", "filterUnits", "glyphRef",
+    "gradientTransform", "gradientUnits", "kernelMatrix", "kernelUnitLength", "keyPoints",
+    "keySplines", "keyTimes", "lengthAdjust", "limitingConeAngle", "markerHeight",
+    "markerUnits", "markerWidth", "maskContentUnits", "maskUnits", "numOctaves",
+    "pathLength", "patternContentUnits", "patternTransform", "patternUnits", "pointsAtX",
+    "pointsAtY", "pointsAtZ", "preserveAlpha", "preserveAspectRatio", "primitiveUnits",
+    "refX", "refY", "repeatCount", "repeatDur", "requiredExtensions", "requiredFeatures",
+    "specularConstant", "specularExponent", "spreadMethod", "startOffset", "stdDeviation",
+    "stitchTiles", "surfaceScale", "systemLanguage", "tableValues", "targetX", "targetY",
+    "textLength", "viewBox", "viewTarget", "xChannelSelector", "yChannelSelector",
+    "zoomAndPan",
+];
+
+// Public identifiers of doctypes triggering the quirks mode.
+const QUIRKS_PUBLIC_ID_PREFIXES: &[&str] = &[
+    "+//silmaril//dtd html pro v0r11 19970101//",
+    "-//as//dtd html 3.0 aswedit + extensions//",
+    "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+    "-//ietf//dtd html 2.0 level 1//",
+    "-//ietf//dtd html 2.0 level 2//",
+    "-//ietf//dtd html 2.0 strict level 1//",
+    "-//ietf//dtd html 2.0 strict level 2//",
+    "-//ietf//dtd html 2.0 strict//",
+    "-//ietf//dtd html 2.0//",
+    "-//ietf//dtd html 2.1e//",
+    "-//ietf//dtd html 3.0//",
+    "-//ietf//dtd html 3.2 final//",
+    "-//ietf//dtd html 3.2//",
+    "-//ietf//dtd html 3//",
+    "-//ietf//dtd html level 0//",
+    "-//ietf//dtd html level 1//",
+    "-//ietf//dtd html level 2//",
+    "-//ietf//dtd html level 3//",
+    "-//ietf//dtd html strict level 0//",
+    "-//ietf//dtd html strict level 1//",
+    "-//ietf//dtd html strict level 2//",
+    "-//ietf//dtd html strict level 3//",
+    "-//ietf//dtd html strict//",
+    "-//ietf//dtd html//",
+    "-//metrius//dtd metrius presentational//",
+    "-//microsoft//dtd internet explorer 2.0 html strict//",
+    "-//microsoft//dtd internet explorer 2.0 html//",
+    "-//microsoft//dtd internet explorer 2.0 tables//",
+    "-//microsoft//dtd internet explorer 3.0 html strict//",
+    "-//microsoft//dtd internet explorer 3.0 html//",
+    "-//microsoft//dtd internet explorer 3.0 tables//",
+    "-//netscape comm. corp.//dtd html//",
+    "-//netscape comm. corp.//dtd strict html//",
+    "-//o'reilly and associates//dtd html 2.0//",
+    "-//o'reilly and associates//dtd html extended 1.0//",
+    "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+    "-//sq//dtd html 2.0 hotmetal + extensions//",
+    "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+    "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//",
+    "-//spyglass//dtd html 2.0 extended//",
+    "-//sun microsystems corp.//dtd hotjava html//",
+    "-//sun microsystems corp.//dtd hotjava strict html//",
+    "-//w3c//dtd html 3 1995-03-24//",
+    "-//w3c//dtd html 3.2 draft//",
+    "-//w3c//dtd html 3.2 final//",
+    "-//w3c//dtd html 3.2//",
+    "-//w3c//dtd html 3.2s draft//",
+    "-//w3c//dtd html 4.0 frameset//",
+    "-//w3c//dtd html 4.0 transitional//",
+    "-//w3c//dtd html experimental 19960712//",
+    "-//w3c//dtd html experimental 970421//",
+    "-//w3c//dtd w3 html//",
+    "-//w3o//dtd w3 html 3.0//",
+    "-//webtechs//dtd mozilla html 2.0//",
+    "-//webtechs//dtd mozilla html//",
+];
+
+// Quirks mode selected by a doctype, see
+// https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode
+fn doctype_quirks_mode(doctype: &Doctype) -> QuirksMode {
+    let public_id = doctype.public_id.as_deref().unwrap_or("").to_ascii_lowercase();
+    let system_id = doctype.system_id.as_deref().map(|id| id.to_ascii_lowercase());
+    let html4_frameset_or_transitional = public_id.starts_with("-//w3c//dtd html 4.01 frameset//")
+        || public_id.starts_with("-//w3c//dtd html 4.01 transitional//");
+
+    if doctype.force_quirks
+        || doctype.name.as_deref() != Some("html")
+        || ["-//w3o//dtd w3 html strict 3.0//en//", "-/w3c/dtd html 4.0 transitional/en", "html"]
+            .contains(&public_id.as_str())
+        || system_id.as_deref() == Some("http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd")
+        || QUIRKS_PUBLIC_ID_PREFIXES.iter().any(|prefix| public_id.starts_with(prefix))
+        || (system_id.is_none() && html4_frameset_or_transitional) {
+        QuirksMode::Quirks
+    } else if public_id.starts_with("-//w3c//dtd xhtml 1.0 frameset//")
+        || public_id.starts_with("-//w3c//dtd xhtml 1.0 transitional//")
+        || (system_id.is_some() && html4_frameset_or_transitional) {
+        QuirksMode::LimitedQuirks
+    } else {
+        QuirksMode::NoQuirks
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == '\t' || c == '\n' || c == '\x0C' || c == '\r' || c == ' '
+}
+
+fn is_hidden_input(tag: &Tag) -> bool {
+    tag.attributes.iter().any(|attr| attr.name == "type" && attr.value.eq_ignore_ascii_case("hidden"))
+}
+
+fn attributes_of(tag: &Tag) -> AttrMap {
+    tag.attributes.iter()
+                  .map(|attr| (attr.name.clone(), attr.value.clone()))
+                  .collect()
+}
+
+fn fix_case(name: &str, names: &[&str]) -> String {
+    names.iter()
+         .find(|fixed| fixed.eq_ignore_ascii_case(name))
+         .map_or_else(|| name.to_string(), |fixed| fixed.to_string())
+}
+
+// Attributes of a foreign element, with their case fixed and the xlink, xml
+// and xmlns attributes put in their namespaces.
+fn foreign_attributes_of(tag: &Tag, namespace: Namespace) -> AttrMap {
+    let mut attributes = AttrMap::new();
+    for attr in &tag.attributes {
+        let name = match namespace {
+            Namespace::Svg => fix_case(&attr.name, SVG_ATTRIBUTE_NAMES),
+            Namespace::MathMl if attr.name == "definitionurl" => "definitionURL".to_string(),
+            _ => attr.name.clone(),
+        };
+        let attr_namespace = match name.as_str() {
+            "xlink:actuate" | "xlink:arcrole" | "xlink:href" | "xlink:role" | "xlink:show"
+            | "xlink:title" | "xlink:type" => Some(Namespace::XLink),
+            "xml:lang" | "xml:space" => Some(Namespace::Xml),
+            "xmlns" | "xmlns:xlink" => Some(Namespace::Xmlns),
+            _ => None,
+        };
+        attributes.insert_ns(attr_namespace, name, attr.value.clone());
+    }
+    attributes
+}
+
+fn is_mathml_text_integration_point(element: &ElementData) -> bool {
+    element.namespace == Namespace::MathMl && ["mi", "mo", "mn", "ms", "mtext"].contains(&element.tag_name.as_str())
+}
+
+// Foreign elements whose content is parsed as HTML.
+fn is_html_integration_point(element: &ElementData) -> bool {
+    match element.namespace {
+        Namespace::MathMl => element.tag_name == "annotation-xml"
+            && element.get_attribute("encoding").is_some_and(|encoding| {
+                encoding.eq_ignore_ascii_case("text/html") || encoding.eq_ignore_ascii_case("application/xhtml+xml")
+            }),
+        Namespace::Svg => ["foreignObject", "desc", "title"].contains(&element.tag_name.as_str()),
+        _ => false,
+    }
+}
+
+pub struct TreeBuilder {
+    // Nodes get the span of the tokens they were created from, implied
+    // elements get an empty span.
+    tree: dom::Tree,
+    mode: InsertionMode,
+    original_mode: InsertionMode,
+    // Stack of template insertion modes, with the mode of the innermost
+    // open <template> last.
+    template_modes: Vec<InsertionMode>,
+    open_elements: Vec<NodeId>,
+    active_formatting: Vec<FormattingEntry>,
+    head: Option<NodeId>,
+    form: Option<NodeId>,
+    frameset_ok: bool,
+    foster_parenting: bool,
+    // Set after <pre> and <listing>, whose first newline is ignored.
+    ignore_lf: bool,
+    pending_table_text: Vec<(char, Span)>,
+    tokenizer_text: Option<TextKind>,
+    quirks_mode: QuirksMode,
+    // Context element when parsing a fragment.
+    context: Option<ElementData>,
+    // Set when the self-closing flag of the current token was honored.
+    self_closing_acknowledged: bool,
+    // Span of the token being processed.
+    span: Span,
+    errors: Vec<ParseError>,
+}
+
+impl TreeBuilder {
+    pub fn new() -> TreeBuilder {
+        TreeBuilder::with_tree(dom::Tree::new_document())
+    }
+
+    fn with_tree(mut tree: dom::Tree) -> TreeBuilder {
+        tree[DOCUMENT].span = Some(Span::at(Location::start()));
+        TreeBuilder {
+            tree,
+            mode: InsertionMode::Initial,
+            original_mode: InsertionMode::Initial,
+            template_modes: Vec::new(),
+            open_elements: Vec::new(),
+            active_formatting: Vec::new(),
+            head: None,
+            form: None,
+            frameset_ok: true,
+            foster_parenting: false,
+            ignore_lf: false,
+            pending_table_text: Vec::new(),
+            tokenizer_text: None,
+            quirks_mode: QuirksMode::NoQuirks,
+            context: None,
+            self_closing_acknowledged: false,
+            span: Span::at(Location::start()),
+            errors: Vec::new(),
+        }
+    }
+
+    // Builder for the HTML fragment parsing algorithm, where the parsed nodes
+    // end up in an <html> element standing for the context element.
+    pub fn new_fragment(context: &ElementData) -> TreeBuilder {
+        let mut builder = TreeBuilder::with_tree(dom::Tree::new(NodeType::DocumentFragment));
+        builder.context = Some(context.clone());
+        let html = builder.create_element(&Tag { name: "html".to_string(), ..Tag::default() });
+        builder.append_node(DOCUMENT, html);
+        builder.open_elements.push(html);
+        if context.is_html() && context.tag_name == "template" {
+            builder.template_modes.push(InsertionMode::InTemplate);
+        }
+        builder.reset_insertion_mode();
+        builder
+    }
+
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn error(&mut self, message: &str) {
+        self.errors.push(ParseError{location: self.span.start, message: message.to_string()});
+    }
+
+    // Tree helpers.
+
+    fn name(&self, node: NodeId) -> &str {
+        match &self.tree[node].node_type {
+            NodeType::Element(element) => &element.tag_name,
+            _ => "",
+        }
+    }
+
+    // Whether the node is an HTML element with one of the names.
+    fn is(&self, node: NodeId, names: &[&str]) -> bool {
+        self.tree[node].element().is_some_and(|element| element.is_html() && names.contains(&element.tag_name.as_str()))
+    }
+
+    // Foreign elements containing HTML, which are special elements and
+    // boundaries of most scopes.
+    fn is_foreign_boundary(&self, node: NodeId) -> bool {
+        self.tree[node].element().is_some_and(|element| match element.namespace {
+            Namespace::MathMl => ["mi", "mo", "mn", "ms", "mtext", "annotation-xml"].contains(&element.tag_name.as_str()),
+            Namespace::Svg => ["foreignObject", "desc", "title"].contains(&element.tag_name.as_str()),
+            _ => false,
+        })
+    }
+
+    fn is_special(&self, node: NodeId) -> bool {
+        self.is(node, SPECIAL_ELEMENTS) || self.is_foreign_boundary(node)
+    }
+
+    // The context element stands for the <html> element of a fragment.
+    fn adjusted_current_element(&self) -> Option<&ElementData> {
+        match (&self.context, self.open_elements.len()) {
+            (Some(context), 1) => Some(context),
+            (_, 0) => None,
+            _ => self.tree[self.current_node()].element(),
+        }
+    }
+
+    // Whether the tokenizer is in SVG or MathML content, where CDATA sections are allowed.
+    pub fn in_foreign_content(&self) -> bool {
+        self.adjusted_current_element().is_some_and(|element| !element.is_html())
+    }
+
+    fn set_quirks_mode(&mut self, quirks_mode: QuirksMode) {
+        self.quirks_mode = quirks_mode;
+        if let NodeType::Document(document) = &mut self.tree[DOCUMENT].node_type {
+            document.quirks_mode = quirks_mode;
+        }
+    }
+
+    fn current_node(&self) -> NodeId {
+        *self.open_elements.last().unwrap_or(&DOCUMENT)
+    }
+
+    fn current_is(&self, names: &[&str]) -> bool {
+        self.is(self.current_node(), names)
+    }
+
+    fn new_node(&mut self, node_type: NodeType) -> NodeId {
+        let node = self.tree.create_node(node_type);
+        self.tree[node].span = Some(self.span);
+        node
+    }
+
+    fn create_element(&mut self, tag: &Tag) -> NodeId {
+        self.new_node(NodeType::Element(ElementData::new(tag.name.clone(), attributes_of(tag))))
+    }
+
+    fn insert_node(&mut self, parent: NodeId, before: Option<NodeId>, node: NodeId) {
+        self.tree.insert_unchecked(parent, node, before);
+    }
+
+    fn append_node(&mut self, parent: NodeId, node: NodeId) {
+        self.tree.insert_unchecked(parent, node, None);
+    }
+
+    // Returns the parent, and the child before which to insert a new node.
+    fn appropriate_insertion_place(&self, override_target: Option<NodeId>) -> (NodeId, Option<NodeId>) {
+        let target = override_target.unwrap_or_else(|| self.current_node());
+        if self.foster_parenting && self.is(target, &["table", "tbody", "tfoot", "thead", "tr"]) {
+            let last_table = self.open_elements.iter().rposition(|&node| self.is(node, &["table"]));
+            let last_template = self.open_elements.iter().rposition(|&node| self.is(node, &["template"]));
+            match last_table {
+                // Template contents are the children of the <template> element.
+                _ if last_template > last_table => (self.open_elements[last_template.unwrap()], None),
+                None => (self.open_elements[0], None),
+                Some(index) => {
+                    let table = self.open_elements[index];
+                    match self.tree[table].parent() {
+                        Some(parent) => (parent, Some(table)),
+                        None => (self.open_elements[index - 1], None),
+                    }
+                },
+            }
+        } else {
+            (target, None)
+        }
+    }
+
+    fn insert_element(&mut self, tag: &Tag) -> NodeId {
+        let (parent, before) = self.appropriate_insertion_place(None);
+        let element = self.create_element(tag);
+        self.insert_node(parent, before, element);
+        self.open_elements.push(element);
+        element
+    }
+
+    fn insert_foreign_element(&mut self, tag: &Tag, namespace: Namespace) -> NodeId {
+        let (parent, before) = self.appropriate_insertion_place(None);
+        let name = match namespace {
+            Namespace::Svg => fix_case(&tag.name, SVG_TAG_NAMES),
+            _ => tag.name.clone(),
+        };
+        let element = self.new_node(NodeType::Element(ElementData::new_ns(namespace, name, foreign_attributes_of(tag, namespace))));
+        self.insert_node(parent, before, element);
+        self.open_elements.push(element);
+        // Foreign elements can be empty, like in XML.
+        if tag.self_closing {
+            self.pop();
+            self.self_closing_acknowledged = true;
+        }
+        element
+    }
+
+    fn insert_element_named(&mut self, name: &str) -> NodeId {
+        let element = self.insert_element(&Tag { name: name.to_string(), ..Tag::default() });
+        self.tree[element].span = Some(Span::at(self.span.start));
+        element
+    }
+
+    // Inserts a comment at the appropriate place, or as the last child of `parent`.
+    fn insert_comment(&mut self, text: String, parent: Option<NodeId>) {
+        let (parent, before) = match parent {
+            Some(parent) => (parent, None),
+            None => self.appropriate_insertion_place(None),
+        };
+        let comment = self.new_node(NodeType::Comment(text));
+        self.insert_node(parent, before, comment);
+    }
+
+    fn insert_character(&mut self, c: char) {
+        let (parent, before) = self.appropriate_insertion_place(None);
+        if parent == DOCUMENT {
+            return;
+        }
+        let previous = match before {
+            Some(before) => self.tree[before].previous_sibling(),
+            None => self.tree[parent].last_child(),
+        };
+        if let Some(previous) = previous {
+            let previous = &mut self.tree[previous];
+            if let NodeType::Text(text) = &mut previous.node_type {
+                text.push(c);
+                if let Some(span) = &mut previous.span {
+                    span.end = self.span.end;
+                }
+                return;
+            }
+        }
+        let text = self.new_node(NodeType::Text(c.to_string()));
+        self.insert_node(parent, before, text);
+    }
+
+    fn pop(&mut self) -> Option<NodeId> {
+        self.open_elements.pop()
+    }
+
+    fn pop_until(&mut self, names: &[&str]) {
+        while let Some(node) = self.pop() {
+            if self.is(node, names) {
+                break;
+            }
+        }
+    }
+
+    fn remove_from_stack(&mut self, node: NodeId) {
+        self.open_elements.retain(|&open| open != node);
+    }
+
+    fn in_scope(&self, names: &[&str], scope: Scope) -> bool {
+        for &node in self.open_elements.iter().rev() {
+            if self.is(node, names) {
+                return true;
+            }
+            let boundary = match scope {
+                Scope::Table => self.is(node, &["html", "table", "template"]),
+                Scope::Select => !self.is(node, &["optgroup", "option"]),
+                _ => {
+                    self.is(node, &["applet", "caption", "html", "table", "td", "th", "marquee",
+                                    "object", "template"])
+                        || self.is_foreign_boundary(node)
+                        || (scope == Scope::ListItem && self.is(node, &["ol", "ul"]))
+                        || (scope == Scope::Button && self.is(node, &["button"]))
+                },
+            };
+            if boundary {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn node_in_scope(&self, target: NodeId) -> bool {
+        for &node in self.open_elements.iter().rev() {
+            if node == target {
+                return true;
+            }
+            if self.is(node, &["applet", "caption", "html", "table", "td", "th", "marquee",
+                               "object", "template"]) || self.is_foreign_boundary(node) {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn generate_implied_end_tags(&mut self, except: Option<&str>) {
+        while self.current_is(IMPLIED_END_TAGS) && Some(self.name(self.current_node())) != except {
+            self.pop();
+        }
+    }
+
+    fn generate_implied_end_tags_thoroughly(&mut self) {
+        while self.current_is(THOROUGHLY_IMPLIED_END_TAGS) {
+            self.pop();
+        }
+    }
+
+    fn template_is_open(&self) -> bool {
+        self.open_elements.iter().any(|&node| self.is(node, &["template"]))
+    }
+
+    fn close_p_element(&mut self) {
+        self.generate_implied_end_tags(Some("p"));
+        if !self.current_is(&["p"]) {
+            self.error("unexpected open element while closing <p>");
+        }
+        self.pop_until(&["p"]);
+    }
+
+    fn close_p_in_button_scope(&mut self) {
+        if self.in_scope(&["p"], Scope::Button) {
+            self.close_p_element();
+        }
+    }
+
+    fn clear_stack_back_to(&mut self, names: &[&str]) {
+        while !self.current_is(names) {
+            self.pop();
+        }
+    }
+
+    fn clear_stack_back_to_table_context(&mut self) {
+        self.clear_stack_back_to(&["table", "template", "html"]);
+    }
+
+    fn clear_stack_back_to_table_body_context(&mut self) {
+        self.clear_stack_back_to(&["tbody", "tfoot", "thead", "template", "html"]);
+    }
+
+    fn clear_stack_back_to_table_row_context(&mut self) {
+        self.clear_stack_back_to(&["tr", "template", "html"]);
+    }
+
+    fn reset_insertion_mode(&mut self) {
+        for (index, &node) in self.open_elements.iter().enumerate().rev() {
+            let last = index == 0;
+            let name = match &self.context {
+                Some(context) if last && context.is_html() => context.tag_name.as_str(),
+                Some(_) if last => "",
+                _ if self.is(node, &[self.name(node)]) => self.name(node),
+                _ => "",
+            };
+            let mode = match name {
+                "select" => {
+                    // A <select> in a table, but not in a template inside the table.
+                    let in_table = !last && self.open_elements[1..index].iter()
+                                                                         .rev()
+                                                                         .take_while(|&&ancestor| !self.is(ancestor, &["template"]))
+                                                                         .any(|&ancestor| self.is(ancestor, &["table"]));
+                    if in_table { InsertionMode::InSelectInTable } else { InsertionMode::InSelect }
+                },
+                "td" | "th" if !last => InsertionMode::InCell,
+                "tr" => InsertionMode::InRow,
+                "tbody" | "thead" | "tfoot" => InsertionMode::InTableBody,
+                "caption" => InsertionMode::InCaption,
+                "colgroup" => InsertionMode::InColumnGroup,
+                "table" => InsertionMode::InTable,
+                "template" => self.template_modes.last().copied().unwrap_or(InsertionMode::InBody),
+                "head" if !last => InsertionMode::InHead,
+                "body" => InsertionMode::InBody,
+                "frameset" => InsertionMode::InFrameset,
+                "html" if self.head.is_none() => InsertionMode::BeforeHead,
+                "html" => InsertionMode::AfterHead,
+                _ if last => InsertionMode::InBody,
+                _ => continue,
+            };
+            self.mode = mode;
+            return;
+        }
+        self.mode = InsertionMode::InBody;
+    }
+
+    // List of active formatting elements.
+
+    fn formatting_index(&self, node: NodeId) -> Option<usize> {
+        self.active_formatting.iter().position(|entry| match entry {
+            FormattingEntry::Element(element, _) => *element == node,
+            FormattingEntry::Marker => false,
+        })
+    }
+
+    // Last formatting element with the given name after the last marker.
+    fn formatting_element_named(&self, name: &str) -> Option<(usize, NodeId)> {
+        for (index, entry) in self.active_formatting.iter().enumerate().rev() {
+            match entry {
+                FormattingEntry::Marker => return None,
+                FormattingEntry::Element(node, _) if self.is(*node, &[name]) => return Some((index, *node)),
+                _ => {},
+            }
+        }
+        None
+    }
+
+    fn push_formatting_element(&mut self, node: NodeId, tag: &Tag) {
+        // Only three identical elements are kept (Noah's Ark clause).
+        let attributes = attributes_of(tag);
+        let mut identical = Vec::new();
+        for (index, entry) in self.active_formatting.iter().enumerate().rev() {
+            match entry {
+                FormattingEntry::Marker => break,
+                FormattingEntry::Element(_, other) => {
+                    if other.name == tag.name && attributes_of(other) == attributes {
+                        identical.push(index);
+                    }
+                },
+            }
+        }
+        if identical.len() >= 3 {
+            self.active_formatting.remove(*identical.last().unwrap());
+        }
+        self.active_formatting.push(FormattingEntry::Element(node, tag.clone()));
+    }
+
+    fn clear_formatting_to_last_marker(&mut self) {
+        while let Some(entry) = self.active_formatting.pop() {
+            if let FormattingEntry::Marker = entry {
+                break;
+            }
+        }
+    }
+
+    fn reconstruct_active_formatting_elements(&mut self) {
+        let is_open = |builder: &TreeBuilder, entry: &FormattingEntry| match entry {
+            FormattingEntry::Marker => true,
+            FormattingEntry::Element(node, _) => builder.open_elements.contains(node),
+        };
+        let mut index = match self.active_formatting.last() {
+            Some(entry) if !is_open(self, entry) => self.active_formatting.len() - 1,
+            _ => return,
+        };
+        while index > 0 && !is_open(self, &self.active_formatting[index - 1]) {
+            index -= 1;
+        }
+        for index in index..self.active_formatting.len() {
+            let (original, tag) = match &self.active_formatting[index] {
+                FormattingEntry::Element(original, tag) => (*original, tag.clone()),
+                FormattingEntry::Marker => continue,
+            };
+            let element = self.insert_element(&tag);
+            self.tree[element].span = self.tree[original].span;
+            self.active_formatting[index] = FormattingEntry::Element(element, tag);
+        }
+    }
+
+    // Returns false if the end tag should be handled as "any other end tag".
+    fn adoption_agency(&mut self, subject: &str) -> bool {
+        let current = self.current_node();
+        if self.is(current, &[subject]) && self.formatting_index(current).is_none() {
+            self.pop();
+            return true;
+        }
+        for _ in 0..8 {
+            let (formatting_index, formatting_element) = match self.formatting_element_named(subject) {
+                Some(found) => found,
+                None => return false,
+            };
+            let formatting_tag = match &self.active_formatting[formatting_index] {
+                FormattingEntry::Element(_, tag) => tag.clone(),
+                FormattingEntry::Marker => unreachable!(),
+            };
+            let stack_index = match self.open_elements.iter().position(|&node| node == formatting_element) {
+                Some(index) => index,
+                None => {
+                    self.error("formatting element is not open");
+                    self.active_formatting.remove(formatting_index);
+                    return true;
+                },
+            };
+            if !self.node_in_scope(formatting_element) {
+                self.error("formatting element is not in scope");
+                return true;
+            }
+            if formatting_element != self.current_node() {
+                self.error(&format!("misnested </{}>", subject));
+            }
+
+            let furthest_block = self.open_elements[stack_index + 1..].iter()
+                                                                       .position(|&node| self.is_special(node))
+                                                                       .map(|index| index + stack_index + 1);
+            let furthest_block_index = match furthest_block {
+                Some(index) => index,
+                None => {
+                    self.open_elements.truncate(stack_index);
+                    self.active_formatting.remove(formatting_index);
+                    return true;
+                },
+            };
+            let furthest_block = self.open_elements[furthest_block_index];
+            let common_ancestor = self.open_elements[stack_index - 1];
+            let mut bookmark = formatting_index;
+
+            let mut node_index = furthest_block_index;
+            let mut last_node = furthest_block;
+            let mut inner_loop_counter = 0;
+            loop {
+                inner_loop_counter += 1;
+                node_index -= 1;
+                let node = self.open_elements[node_index];
+                if node == formatting_element {
+                    break;
+                }
+                if inner_loop_counter > 3 {
+                    if let Some(index) = self.formatting_index(node) {
+                        self.active_formatting.remove(index);
+                        if index < bookmark {
+                            bookmark -= 1;
+                        }
+                    }
+                }
+                let node_formatting_index = match self.formatting_index(node) {
+                    Some(index) => index,
+                    None => {
+                        self.open_elements.remove(node_index);
+                        continue;
+                    },
+                };
+                let tag = match &self.active_formatting[node_formatting_index] {
+                    FormattingEntry::Element(_, tag) => tag.clone(),
+                    FormattingEntry::Marker => unreachable!(),
+                };
+                let new_element = self.create_element(&tag);
+                self.tree[new_element].span = self.tree[node].span;
+                self.active_formatting[node_formatting_index] = FormattingEntry::Element(new_element, tag);
+                self.open_elements[node_index] = new_element;
+                if last_node == furthest_block {
+                    bookmark = node_formatting_index + 1;
+                }
+                self.append_node(new_element, last_node);
+                last_node = new_element;
+            }
+
+            let (parent, before) = self.appropriate_insertion_place(Some(common_ancestor));
+            self.insert_node(parent, before, last_node);
+
+            let new_element = self.create_element(&formatting_tag);
+            self.tree[new_element].span = self.tree[formatting_element].span;
+            while let Some(child) = self.tree[furthest_block].first_child() {
+                self.append_node(new_element, child);
+            }
+            self.append_node(furthest_block, new_element);
+
+            let formatting_index = self.formatting_index(formatting_element).unwrap();
+            self.active_formatting.remove(formatting_index);
+            if formatting_index < bookmark {
+                bookmark -= 1;
+            }
+            let bookmark = bookmark.min(self.active_formatting.len());
+            self.active_formatting.insert(bookmark, FormattingEntry::Element(new_element, formatting_tag));
+
+            self.remove_from_stack(formatting_element);
+            let furthest_block_index = self.open_elements.iter().position(|&node| node == furthest_block).unwrap();
+            self.open_elements.insert(furthest_block_index + 1, new_element);
+        }
+        true
+    }
+
+    // Token processing.
+
+    pub fn process_token(&mut self, token: Token, span: Span) {
+        self.span = span;
+        if self.ignore_lf {
+            self.ignore_lf = false;
+            if token == Token::Character('\n') {
+                return;
+            }
+        }
+        let self_closing = match &token {
+            Token::StartTag(tag) if tag.self_closing && !VOID_ELEMENTS.contains(&tag.name.as_str()) => Some(tag.name.clone()),
+            _ => None,
+        };
+        self.self_closing_acknowledged = false;
+        self.process(token);
+        if let Some(name) = self_closing {
+            if !self.self_closing_acknowledged {
+                self.error(&format!("self-closing syntax on non-void element <{}/>", name));
+            }
+        }
+    }
+
+    // Tree construction dispatcher, choosing between the rules of the
+    // insertion mode and the ones of foreign content.
+    fn process(&mut self, token: Token) {
+        let foreign = match self.adjusted_current_element() {
+            Some(element) if !element.is_html() => {
+                let is_character = matches!(token, Token::Character(_));
+                let start_tag = match &token {
+                    Token::StartTag(tag) => Some(tag.name.as_str()),
+                    _ => None,
+                };
+                !(is_mathml_text_integration_point(element)
+                      && (is_character || start_tag.is_some_and(|name| name != "mglyph" && name != "malignmark"))
+                  || element.namespace == Namespace::MathMl && element.tag_name == "annotation-xml" && start_tag == Some("svg")
+                  || is_html_integration_point(element) && (is_character || start_tag.is_some()))
+            },
+            _ => false,
+        };
+        if foreign {
+            self.foreign_content(token);
+        } else {
+            self.process_in_mode(token);
+        }
+    }
+
+    fn process_in_mode(&mut self, token: Token) {
+        match self.mode {
+            InsertionMode::Initial => self.initial(token),
+            InsertionMode::BeforeHtml => self.before_html(token),
+            InsertionMode::BeforeHead => self.before_head(token),
+            InsertionMode::InHead => self.in_head(token),
+            InsertionMode::AfterHead => self.after_head(token),
+            InsertionMode::InBody => self.in_body(token),
+            InsertionMode::Text => self.text(token),
+            InsertionMode::InTable => self.in_table(token),
+            InsertionMode::InTableText => self.in_table_text(token),
+            InsertionMode::InCaption => self.in_caption(token),
+            InsertionMode::InColumnGroup => self.in_column_group(token),
+            InsertionMode::InTableBody => self.in_table_body(token),
+            InsertionMode::InRow => self.in_row(token),
+            InsertionMode::InCell => self.in_cell(token),
+            InsertionMode::InSelect => self.in_select(token),
+            InsertionMode::InSelectInTable => self.in_select_in_table(token),
+            InsertionMode::InTemplate => self.in_template(token),
+            InsertionMode::AfterBody => self.after_body(token),
+            InsertionMode::InFrameset => self.in_frameset(token),
+            InsertionMode::AfterFrameset => self.after_frameset(token),
+            InsertionMode::AfterAfterBody => self.after_after_body(token),
+            InsertionMode::AfterAfterFrameset => self.after_after_frameset(token),
+        }
+    }
+
+    fn initial(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => {},
+            Token::Comment(text) => self.insert_comment(text, Some(DOCUMENT)),
+            Token::Doctype(doctype) => {
+                let valid = doctype.name.as_deref() == Some("html")
+                    && doctype.public_id.is_none()
+                    && doctype.system_id.as_deref().is_none_or(|id| id == "about:legacy-compat");
+                if !valid {
+                    self.error("unexpected doctype");
+                }
+                self.set_quirks_mode(doctype_quirks_mode(&doctype));
+                let node = self.new_node(NodeType::Doctype(DoctypeData {
+                    name: doctype.name.unwrap_or_default(),
+                    public_id: doctype.public_id.unwrap_or_default(),
+                    system_id: doctype.system_id.unwrap_or_default(),
+                }));
+                self.append_node(DOCUMENT, node);
+                self.mode = InsertionMode::BeforeHtml;
+            },
+            _ => {
+                self.error("missing doctype");
+                self.set_quirks_mode(QuirksMode::Quirks);
+                self.mode = InsertionMode::BeforeHtml;
+                self.process(token);
+            },
+        }
+    }
+
+    fn before_html(&mut self, token: Token) {
+        match token {
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::Comment(text) => self.insert_comment(text, Some(DOCUMENT)),
+            Token::Character(c) if is_whitespace(c) => {},
+            Token::StartTag(ref tag) if tag.name == "html" => {
+                let html = self.create_element(tag);
+                self.append_node(DOCUMENT, html);
+                self.open_elements.push(html);
+                self.mode = InsertionMode::BeforeHead;
+            },
+            Token::EndTag(ref tag) if !["head", "body", "html", "br"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            _ => {
+                let html = self.create_element(&Tag { name: "html".to_string(), ..Tag::default() });
+                self.tree[html].span = Some(Span::at(self.span.start));
+                self.append_node(DOCUMENT, html);
+                self.open_elements.push(html);
+                self.mode = InsertionMode::BeforeHead;
+                self.process(token);
+            },
+        }
+    }
+
+    fn before_head(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => {},
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) if tag.name == "html" => self.in_body(token),
+            Token::StartTag(ref tag) if tag.name == "head" => {
+                self.head = Some(self.insert_element(tag));
+                self.mode = InsertionMode::InHead;
+            },
+            Token::EndTag(ref tag) if !["head", "body", "html", "br"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            _ => {
+                self.head = Some(self.insert_element_named("head"));
+                self.mode = InsertionMode::InHead;
+                self.process(token);
+            },
+        }
+    }
+
+    fn in_head(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => self.insert_character(c),
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) => match tag.name.as_str() {
+                "html" => self.in_body(token),
+                "base" | "basefont" | "bgsound" | "link" | "meta" => {
+                    self.insert_element(tag);
+                    self.pop();
+                },
+                "title" => self.parse_text_element(tag, TextKind::Rcdata),
+                "noscript" | "noframes" | "style" => self.parse_text_element(tag, TextKind::Rawtext),
+                "script" => self.parse_text_element(tag, TextKind::ScriptData),
+                "template" => {
+                    self.insert_element(tag);
+                    self.active_formatting.push(FormattingEntry::Marker);
+                    self.frameset_ok = false;
+                    self.mode = InsertionMode::InTemplate;
+                    self.template_modes.push(InsertionMode::InTemplate);
+                },
+                "head" => self.error("unexpected <head>"),
+                _ => {
+                    self.pop();
+                    self.mode = InsertionMode::AfterHead;
+                    self.process(token);
+                },
+            },
+            Token::EndTag(ref tag) => match tag.name.as_str() {
+                "head" => {
+                    self.pop();
+                    self.mode = InsertionMode::AfterHead;
+                },
+                "body" | "html" | "br" => {
+                    self.pop();
+                    self.mode = InsertionMode::AfterHead;
+                    self.process(token);
+                },
+                "template" => self.close_template(),
+                _ => self.error(&format!("ignoring stray end tag </{}>", tag.name)),
+            },
+            Token::Character(_) => {
+                self.pop();
+                self.mode = InsertionMode::AfterHead;
+                self.process(token);
+            },
+        }
+    }
+
+    fn close_template(&mut self) {
+        if !self.template_is_open() {
+            self.error("ignoring stray end tag </template>");
+            return;
+        }
+        self.generate_implied_end_tags_thoroughly();
+        if !self.current_is(&["template"]) {
+            self.error("</template> implicitly closes open elements");
+        }
+        self.pop_until(&["template"]);
+        self.clear_formatting_to_last_marker();
+        self.template_modes.pop();
+        self.reset_insertion_mode();
+    }
+
+    fn after_head(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => self.insert_character(c),
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) => match tag.name.as_str() {
+                "html" => self.in_body(token),
+                "body" => {
+                    self.insert_element(tag);
+                    self.frameset_ok = false;
+                    self.mode = InsertionMode::InBody;
+                },
+                "frameset" => {
+                    self.insert_element(tag);
+                    self.mode = InsertionMode::InFrameset;
+                },
+                "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes" | "script"
+                | "style" | "template" | "title" => {
+                    self.error(&format!("<{}> outside of <head>", tag.name));
+                    let head = self.head.unwrap();
+                    self.open_elements.push(head);
+                    self.in_head(token);
+                    self.remove_from_stack(head);
+                },
+                "head" => self.error("unexpected <head>"),
+                _ => {
+                    self.insert_element_named("body");
+                    self.mode = InsertionMode::InBody;
+                    self.process(token);
+                },
+            },
+            Token::EndTag(ref tag) if tag.name == "template" => self.in_head(token),
+            Token::EndTag(ref tag) if !["body", "html", "br"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            _ => {
+                self.insert_element_named("body");
+                self.mode = InsertionMode::InBody;
+                self.process(token);
+            },
+        }
+    }
+
+    fn in_body(&mut self, token: Token) {
+        match token {
+            Token::Character('\0') => self.error("unexpected null character"),
+            Token::Character(c) => {
+                self.reconstruct_active_formatting_elements();
+                self.insert_character(c);
+                if !is_whitespace(c) {
+                    self.frameset_ok = false;
+                }
+            },
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(tag) => self.in_body_start_tag(tag),
+            Token::EndTag(tag) => self.in_body_end_tag(tag),
+        }
+    }
+
+    fn in_body_start_tag(&mut self, tag: Tag) {
+        let name = tag.name.as_str();
+        match name {
+            "html" => {
+                self.error("unexpected <html>");
+                self.add_missing_attributes(self.open_elements[0], &tag);
+            },
+            "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes" | "script" | "style"
+            | "template" | "title" => self.in_head(Token::StartTag(tag)),
+            "body" => {
+                self.error("unexpected <body>");
+                if self.open_elements.len() > 1 && self.is(self.open_elements[1], &["body"]) && !self.template_is_open() {
+                    self.frameset_ok = false;
+                    self.add_missing_attributes(self.open_elements[1], &tag);
+                }
+            },
+            "frameset" => {
+                self.error("unexpected <frameset>");
+                if self.open_elements.len() > 1 && self.is(self.open_elements[1], &["body"]) && self.frameset_ok {
+                    let body = self.open_elements[1];
+                    self.tree.detach(body);
+                    self.open_elements.truncate(1);
+                    self.insert_element(&tag);
+                    self.mode = InsertionMode::InFrameset;
+                }
+            },
+            _ if BLOCK_ELEMENTS.contains(&name) => {
+                self.close_p_in_button_scope();
+                self.insert_element(&tag);
+            },
+            _ if HEADINGS.contains(&name) => {
+                self.close_p_in_button_scope();
+                if self.current_is(HEADINGS) {
+                    self.error(&format!("<{}> inside another heading", name));
+                    self.pop();
+                }
+                self.insert_element(&tag);
+            },
+            "plaintext" => {
+                self.close_p_in_button_scope();
+                self.insert_element(&tag);
+                self.tokenizer_text = Some(TextKind::Plaintext);
+            },
+            "textarea" => {
+                self.ignore_lf = true;
+                self.frameset_ok = false;
+                self.parse_text_element(&tag, TextKind::Rcdata);
+            },
+            "xmp" => {
+                self.close_p_in_button_scope();
+                self.reconstruct_active_formatting_elements();
+                self.frameset_ok = false;
+                self.parse_text_element(&tag, TextKind::Rawtext);
+            },
+            "iframe" => {
+                self.frameset_ok = false;
+                self.parse_text_element(&tag, TextKind::Rawtext);
+            },
+            // Like browsers, the content of <noscript> is not parsed as markup,
+            // as if scripting was enabled.
+            "noembed" | "noscript" => self.parse_text_element(&tag, TextKind::Rawtext),
+            "pre" | "listing" => {
+                self.close_p_in_button_scope();
+                self.insert_element(&tag);
+                self.ignore_lf = true;
+                self.frameset_ok = false;
+            },
+            "form" => {
+                if self.form.is_some() && !self.template_is_open() {
+                    self.error("nested <form>");
+                } else {
+                    self.close_p_in_button_scope();
+                    let form = self.insert_element(&tag);
+                    if !self.template_is_open() {
+                        self.form = Some(form);
+                    }
+                }
+            },
+            "li" | "dd" | "dt" => {
+                self.frameset_ok = false;
+                let closed: &[&str] = if name == "li" { &["li"] } else { &["dd", "dt"] };
+                for index in (0..self.open_elements.len()).rev() {
+                    let node = self.open_elements[index];
+                    if self.is(node, closed) {
+                        let node_name = self.name(node).to_string();
+                        self.generate_implied_end_tags(Some(&node_name));
+                        if !self.current_is(&[&node_name]) {
+                            self.error(&format!("unexpected open element while closing <{}>", node_name));
+                        }
+                        self.pop_until(&[&node_name]);
+                        break;
+                    }
+                    if self.is_special(node) && !self.is(node, &["address", "div", "p"]) {
+                        break;
+                    }
+                }
+                self.close_p_in_button_scope();
+                self.insert_element(&tag);
+            },
+            "button" => {
+                if self.in_scope(&["button"], Scope::Default) {
+                    self.error("nested <button>");
+                    self.generate_implied_end_tags(None);
+                    self.pop_until(&["button"]);
+                }
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+                self.frameset_ok = false;
+            },
+            "a" => {
+                if let Some((_, element)) = self.formatting_element_named("a") {
+                    self.error("nested <a>");
+                    self.adoption_agency("a");
+                    if let Some(index) = self.formatting_index(element) {
+                        self.active_formatting.remove(index);
+                    }
+                    self.remove_from_stack(element);
+                }
+                self.reconstruct_active_formatting_elements();
+                let element = self.insert_element(&tag);
+                self.push_formatting_element(element, &tag);
+            },
+            "nobr" => {
+                self.reconstruct_active_formatting_elements();
+                if self.in_scope(&["nobr"], Scope::Default) {
+                    self.error("nested <nobr>");
+                    self.adoption_agency("nobr");
+                    self.reconstruct_active_formatting_elements();
+                }
+                let element = self.insert_element(&tag);
+                self.push_formatting_element(element, &tag);
+            },
+            _ if FORMATTING_ELEMENTS.contains(&name) => {
+                self.reconstruct_active_formatting_elements();
+                let element = self.insert_element(&tag);
+                self.push_formatting_element(element, &tag);
+            },
+            "applet" | "marquee" | "object" => {
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+                self.active_formatting.push(FormattingEntry::Marker);
+                self.frameset_ok = false;
+            },
+            "table" => {
+                if self.quirks_mode != QuirksMode::Quirks {
+                    self.close_p_in_button_scope();
+                }
+                self.insert_element(&tag);
+                self.frameset_ok = false;
+                self.mode = InsertionMode::InTable;
+            },
+            "area" | "br" | "embed" | "img" | "keygen" | "wbr" => {
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+                self.pop();
+                self.frameset_ok = false;
+            },
+            "input" => {
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+                self.pop();
+                if !is_hidden_input(&tag) {
+                    self.frameset_ok = false;
+                }
+            },
+            "param" | "source" | "track" => {
+                self.insert_element(&tag);
+                self.pop();
+            },
+            "hr" => {
+                self.close_p_in_button_scope();
+                self.insert_element(&tag);
+                self.pop();
+                self.frameset_ok = false;
+            },
+            "select" => {
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+                self.frameset_ok = false;
+                self.mode = match self.mode {
+                    InsertionMode::InTable | InsertionMode::InCaption | InsertionMode::InTableBody
+                    | InsertionMode::InRow | InsertionMode::InCell => InsertionMode::InSelectInTable,
+                    _ => InsertionMode::InSelect,
+                };
+            },
+            "image" => {
+                self.error("<image> instead of <img>");
+                self.in_body_start_tag(Tag { name: "img".to_string(), ..tag });
+            },
+            "optgroup" | "option" => {
+                if self.current_is(&["option"]) {
+                    self.pop();
+                }
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+            },
+            "rb" | "rtc" => {
+                if self.in_scope(&["ruby"], Scope::Default) {
+                    self.generate_implied_end_tags(None);
+                    if !self.current_is(&["ruby"]) {
+                        self.error(&format!("<{}> outside of <ruby>", name));
+                    }
+                }
+                self.insert_element(&tag);
+            },
+            "rp" | "rt" => {
+                // Annotations can be in an <rtc>, which stays open.
+                if self.in_scope(&["ruby"], Scope::Default) {
+                    self.generate_implied_end_tags(Some("rtc"));
+                    if !self.current_is(&["rtc", "ruby"]) {
+                        self.error(&format!("<{}> outside of <ruby> or <rtc>", name));
+                    }
+                }
+                self.insert_element(&tag);
+            },
+            "math" | "svg" => {
+                self.reconstruct_active_formatting_elements();
+                let namespace = if name == "math" { Namespace::MathMl } else { Namespace::Svg };
+                self.insert_foreign_element(&tag, namespace);
+            },
+            "caption" | "col" | "colgroup" | "frame" | "head" | "tbody" | "td" | "tfoot" | "th"
+            | "thead" | "tr" => self.error(&format!("ignoring <{}> outside of its context", name)),
+            _ => {
+                self.reconstruct_active_formatting_elements();
+                self.insert_element(&tag);
+                // Deliberate deviation from browsers: the self-closing syntax is
+                // honored on unknown elements so that <foo/> does not swallow the
+                // content after it, and is acknowledged so it reports no error.
+                if tag.self_closing {
+                    self.pop();
+                    self.self_closing_acknowledged = true;
+                }
+            },
+        }
+    }
+
+    fn in_body_end_tag(&mut self, tag: Tag) {
+        let name = tag.name.as_str();
+        match name {
+            "body" | "html" => {
+                if !self.in_scope(&["body"], Scope::Default) {
+                    self.error(&format!("ignoring </{}> without open <body>", name));
+                    return;
+                }
+                self.check_unclosed_elements();
+                self.mode = InsertionMode::AfterBody;
+                if name == "html" {
+                    self.process(Token::EndTag(tag));
+                }
+            },
+            _ if CLOSABLE_BLOCK_ELEMENTS.contains(&name) => {
+                if !self.in_scope(&[name], Scope::Default) {
+                    self.error(&format!("ignoring stray end tag </{}>", name));
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.current_is(&[name]) {
+                    self.error(&format!("</{}> implicitly closes <{}>", name, self.name(self.current_node())));
+                }
+                self.pop_until(&[name]);
+            },
+            "br" => {
+                self.error("</br> instead of <br>");
+                self.in_body_start_tag(Tag { name: "br".to_string(), ..Tag::default() });
+            },
+            "template" => self.in_head(Token::EndTag(tag)),
+            // The form element pointer is not set inside templates.
+            "form" if self.template_is_open() => {
+                if !self.in_scope(&["form"], Scope::Default) {
+                    self.error("ignoring stray end tag </form>");
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.current_is(&["form"]) {
+                    self.error("</form> implicitly closes open elements");
+                }
+                self.pop_until(&["form"]);
+            },
+            "form" => {
+                let form = self.form.take();
+                match form {
+                    Some(form) if self.node_in_scope(form) => {
+                        self.generate_implied_end_tags(None);
+                        if self.current_node() != form {
+                            self.error("</form> implicitly closes open elements");
+                        }
+                        self.remove_from_stack(form);
+                    },
+                    _ => self.error("ignoring stray end tag </form>"),
+                }
+            },
+            "p" => {
+                if !self.in_scope(&["p"], Scope::Button) {
+                    self.error("</p> without open <p>");
+                    self.insert_element_named("p");
+                }
+                self.close_p_element();
+            },
+            "li" | "dd" | "dt" => {
+                let scope = if name == "li" { Scope::ListItem } else { Scope::Default };
+                if !self.in_scope(&[name], scope) {
+                    self.error(&format!("ignoring stray end tag </{}>", name));
+                    return;
+                }
+                self.generate_implied_end_tags(Some(name));
+                if !self.current_is(&[name]) {
+                    self.error(&format!("</{}> implicitly closes <{}>", name, self.name(self.current_node())));
+                }
+                self.pop_until(&[name]);
+            },
+            _ if HEADINGS.contains(&name) => {
+                if !self.in_scope(HEADINGS, Scope::Default) {
+                    self.error(&format!("ignoring stray end tag </{}>", name));
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.current_is(&[name]) {
+                    self.error(&format!("</{}> implicitly closes <{}>", name, self.name(self.current_node())));
+                }
+                self.pop_until(HEADINGS);
+            },
+            _ if FORMATTING_ELEMENTS.contains(&name) => {
+                if !self.adoption_agency(name) {
+                    self.any_other_end_tag(name);
+                }
+            },
+            "applet" | "marquee" | "object" => {
+                if !self.in_scope(&[name], Scope::Default) {
+                    self.error(&format!("ignoring stray end tag </{}>", name));
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.current_is(&[name]) {
+                    self.error(&format!("</{}> implicitly closes <{}>", name, self.name(self.current_node())));
+                }
+                self.pop_until(&[name]);
+                self.clear_formatting_to_last_marker();
+            },
+            _ => self.any_other_end_tag(name),
+        }
+    }
+
+    fn any_other_end_tag(&mut self, name: &str) {
+        for index in (0..self.open_elements.len()).rev() {
+            let node = self.open_elements[index];
+            if self.is(node, &[name]) {
+                self.generate_implied_end_tags(Some(name));
+                if node != self.current_node() {
+                    self.error(&format!("</{}> implicitly closes <{}>", name, self.name(self.current_node())));
+                }
+                self.open_elements.truncate(index);
+                return;
+            }
+            if self.is_special(node) {
+                self.error(&format!("ignoring stray end tag </{}>", name));
+                return;
+            }
+        }
+    }
+
+    fn add_missing_attributes(&mut self, element: NodeId, tag: &Tag) {
+        if let NodeType::Element(element) = &mut self.tree[element].node_type {
+            let attributes = element.attributes_mut();
+            for attr in &tag.attributes {
+                if !attributes.contains_key(&attr.name) {
+                    attributes.insert(attr.name.clone(), attr.value.clone());
+                }
+            }
+        }
+    }
+
+    fn check_unclosed_elements(&mut self) {
+        let unclosed = self.open_elements.iter().find(|&&node| !self.is(node, OPTIONAL_END_TAGS));
+        if let Some(&node) = unclosed {
+            let message = format!("<{}> is not closed", self.name(node));
+            self.error(&message);
+        }
+    }
+
+    // Inserts an element whose content is parsed as text by the tokenizer.
+    fn parse_text_element(&mut self, tag: &Tag, kind: TextKind) {
+        self.insert_element(tag);
+        self.tokenizer_text = Some(kind);
+        self.original_mode = self.mode;
+        self.mode = InsertionMode::Text;
+    }
+
+    // Kind of text the tokenizer must switch to after the last processed token.
+    pub fn take_tokenizer_text(&mut self) -> Option<TextKind> {
+        self.tokenizer_text.take()
+    }
+
+    fn text(&mut self, token: Token) {
+        match token {
+            Token::Character(c) => self.insert_character(c),
+            // Scripts are not executed, so </script> is handled like other end tags.
+            Token::EndTag(_) => {
+                self.pop();
+                self.mode = self.original_mode;
+            },
+            _ => self.error("unexpected markup in text-only element"),
+        }
+    }
+
+    // Rules for parsing tokens in foreign content, see
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+    fn foreign_content(&mut self, token: Token) {
+        match token {
+            Token::Character('\0') => {
+                self.error("unexpected null character");
+                self.insert_character('\u{fffd}');
+            },
+            Token::Character(c) => {
+                self.insert_character(c);
+                if !is_whitespace(c) {
+                    self.frameset_ok = false;
+                }
+            },
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) if FOREIGN_BREAKOUT_ELEMENTS.contains(&tag.name.as_str())
+                || (tag.name == "font" && tag.attributes.iter().any(|attr| ["color", "face", "size"].contains(&attr.name.as_str()))) => {
+                self.error(&format!("<{}> in foreign content", tag.name));
+                self.leave_foreign_content(token);
+            },
+            Token::EndTag(ref tag) if tag.name == "br" || tag.name == "p" => {
+                self.error(&format!("</{}> in foreign content", tag.name));
+                self.leave_foreign_content(token);
+            },
+            Token::StartTag(tag) => {
+                let namespace = self.adjusted_current_element().unwrap().namespace;
+                self.insert_foreign_element(&tag, namespace);
+            },
+            Token::EndTag(tag) => {
+                let mut index = self.open_elements.len() - 1;
+                if !self.name(self.open_elements[index]).eq_ignore_ascii_case(&tag.name) {
+                    self.error(&format!("</{}> does not match the open <{}>", tag.name, self.name(self.open_elements[index])));
+                }
+                while index > 0 {
+                    let node = self.open_elements[index];
+                    if self.name(node).eq_ignore_ascii_case(&tag.name) {
+                        self.open_elements.truncate(index);
+                        return;
+                    }
+                    index -= 1;
+                    if self.tree[self.open_elements[index]].element().is_some_and(ElementData::is_html) {
+                        self.process_in_mode(Token::EndTag(tag));
+                        return;
+                    }
+                }
+            },
+        }
+    }
+
+    // Closes the foreign elements up to an HTML element or an integration point
+    // and processes the token as HTML.
+    fn leave_foreign_content(&mut self, token: Token) {
+        while let Some(element) = self.tree[self.current_node()].element() {
+            if element.is_html() || is_mathml_text_integration_point(element) || is_html_integration_point(element) {
+                break;
+            }
+            self.pop();
+        }
+        self.process_in_mode(token);
+    }
+
+    fn in_table(&mut self, token: Token) {
+        match token {
+            Token::Character(_) if self.current_is(&["table", "tbody", "template", "tfoot", "thead", "tr"]) => {
+                self.pending_table_text.clear();
+                self.original_mode = self.mode;
+                self.mode = InsertionMode::InTableText;
+                self.process(token);
+            },
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) => match tag.name.as_str() {
+                "caption" => {
+                    self.clear_stack_back_to_table_context();
+                    self.active_formatting.push(FormattingEntry::Marker);
+                    self.insert_element(tag);
+                    self.mode = InsertionMode::InCaption;
+                },
+                "colgroup" => {
+                    self.clear_stack_back_to_table_context();
+                    self.insert_element(tag);
+                    self.mode = InsertionMode::InColumnGroup;
+                },
+                "col" => {
+                    self.clear_stack_back_to_table_context();
+                    self.insert_element_named("colgroup");
+                    self.mode = InsertionMode::InColumnGroup;
+                    self.process(token);
+                },
+                "tbody" | "tfoot" | "thead" => {
+                    self.clear_stack_back_to_table_context();
+                    self.insert_element(tag);
+                    self.mode = InsertionMode::InTableBody;
+                },
+                "td" | "th" | "tr" => {
+                    self.clear_stack_back_to_table_context();
+                    self.insert_element_named("tbody");
+                    self.mode = InsertionMode::InTableBody;
+                    self.process(token);
+                },
+                "table" => {
+                    self.error("nested <table>");
+                    if self.in_scope(&["table"], Scope::Table) {
+                        self.pop_until(&["table"]);
+                        self.reset_insertion_mode();
+                        self.process(token);
+                    }
+                },
+                "style" | "script" | "template" => self.in_head(token),
+                "input" if is_hidden_input(tag) => {
+                    self.error("<input> inside a table");
+                    self.insert_element(tag);
+                    self.pop();
+                },
+                "form" => {
+                    self.error("<form> inside a table");
+                    if self.form.is_none() && !self.template_is_open() {
+                        self.form = Some(self.insert_element(tag));
+                        self.pop();
+                    }
+                },
+                _ => self.in_table_anything_else(token),
+            },
+            Token::EndTag(ref tag) => match tag.name.as_str() {
+                "table" => {
+                    if !self.in_scope(&["table"], Scope::Table) {
+                        self.error("ignoring stray end tag </table>");
+                        return;
+                    }
+                    self.pop_until(&["table"]);
+                    self.reset_insertion_mode();
+                },
+                "body" | "caption" | "col" | "colgroup" | "html" | "tbody" | "td" | "tfoot" | "th"
+                | "thead" | "tr" => self.error(&format!("ignoring stray end tag </{}>", tag.name)),
+                "template" => self.in_head(token),
+                _ => self.in_table_anything_else(token),
+            },
+            Token::Character(_) => self.in_table_anything_else(token),
+        }
+    }
+
+    fn in_table_anything_else(&mut self, token: Token) {
+        self.error("misplaced content in table");
+        self.foster_parenting = true;
+        self.in_body(token);
+        self.foster_parenting = false;
+    }
+
+    fn in_table_text(&mut self, token: Token) {
+        match token {
+            Token::Character('\0') => self.error("unexpected null character"),
+            Token::Character(c) => self.pending_table_text.push((c, self.span)),
+            _ => {
+                self.flush_pending_table_text();
+                self.process(token);
+            },
+        }
+    }
+
+    fn flush_pending_table_text(&mut self) {
+        let text = std::mem::take(&mut self.pending_table_text);
+        let span = self.span;
+        if text.iter().any(|&(c, _)| !is_whitespace(c)) {
+            self.span = text[0].1;
+            self.error("misplaced text in table");
+            self.foster_parenting = true;
+            for (c, char_span) in text {
+                self.span = char_span;
+                self.in_body(Token::Character(c));
+            }
+            self.foster_parenting = false;
+        } else {
+            for (c, char_span) in text {
+                self.span = char_span;
+                self.insert_character(c);
+            }
+        }
+        self.span = span;
+        self.mode = self.original_mode;
+    }
+
+    fn close_caption(&mut self) -> bool {
+        if !self.in_scope(&["caption"], Scope::Table) {
+            self.error("no <caption> to close");
+            return false;
+        }
+        self.generate_implied_end_tags(None);
+        if !self.current_is(&["caption"]) {
+            self.error("</caption> implicitly closes open elements");
+        }
+        self.pop_until(&["caption"]);
+        self.clear_formatting_to_last_marker();
+        self.mode = InsertionMode::InTable;
+        true
+    }
+
+    fn in_caption(&mut self, token: Token) {
+        match token {
+            Token::EndTag(ref tag) if tag.name == "caption" => {
+                self.close_caption();
+            },
+            Token::StartTag(ref tag) if ["caption", "col", "colgroup", "tbody", "td", "tfoot", "th",
+                                         "thead", "tr"].contains(&tag.name.as_str()) => {
+                if self.close_caption() {
+                    self.process(token);
+                }
+            },
+            Token::EndTag(ref tag) if tag.name == "table" => {
+                if self.close_caption() {
+                    self.process(token);
+                }
+            },
+            Token::EndTag(ref tag) if ["body", "col", "colgroup", "html", "tbody", "td", "tfoot", "th",
+                                       "thead", "tr"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            _ => self.in_body(token),
+        }
+    }
+
+    fn in_column_group(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => self.insert_character(c),
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) if tag.name == "html" => self.in_body(token),
+            Token::StartTag(ref tag) | Token::EndTag(ref tag) if tag.name == "template" => self.in_head(token),
+            Token::StartTag(ref tag) if tag.name == "col" => {
+                self.insert_element(tag);
+                self.pop();
+            },
+            Token::EndTag(ref tag) if tag.name == "colgroup" => {
+                if self.current_is(&["colgroup"]) {
+                    self.pop();
+                    self.mode = InsertionMode::InTable;
+                } else {
+                    self.error("ignoring stray end tag </colgroup>");
+                }
+            },
+            Token::EndTag(ref tag) if tag.name == "col" => self.error("ignoring stray end tag </col>"),
+            _ => {
+                if self.current_is(&["colgroup"]) {
+                    self.pop();
+                    self.mode = InsertionMode::InTable;
+                    self.process(token);
+                } else {
+                    self.error("unexpected content in <colgroup>");
+                }
+            },
+        }
+    }
+
+    fn in_table_body(&mut self, token: Token) {
+        match token {
+            Token::StartTag(ref tag) if tag.name == "tr" => {
+                self.clear_stack_back_to_table_body_context();
+                self.insert_element(tag);
+                self.mode = InsertionMode::InRow;
+            },
+            Token::StartTag(ref tag) if tag.name == "th" || tag.name == "td" => {
+                self.error(&format!("<{}> outside of a row", tag.name));
+                self.clear_stack_back_to_table_body_context();
+                self.insert_element_named("tr");
+                self.mode = InsertionMode::InRow;
+                self.process(token);
+            },
+            Token::EndTag(ref tag) if TABLE_SECTIONS.contains(&tag.name.as_str()) => {
+                if !self.in_scope(&[&tag.name], Scope::Table) {
+                    self.error(&format!("ignoring stray end tag </{}>", tag.name));
+                    return;
+                }
+                self.clear_stack_back_to_table_body_context();
+                self.pop();
+                self.mode = InsertionMode::InTable;
+            },
+            Token::StartTag(ref tag) if ["caption", "col", "colgroup", "tbody", "tfoot",
+                                         "thead"].contains(&tag.name.as_str()) => {
+                self.close_table_section(token);
+            },
+            Token::EndTag(ref tag) if tag.name == "table" => self.close_table_section(token),
+            Token::EndTag(ref tag) if ["body", "caption", "col", "colgroup", "html", "td", "th",
+                                       "tr"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            _ => self.in_table(token),
+        }
+    }
+
+    fn close_table_section(&mut self, token: Token) {
+        if !self.in_scope(TABLE_SECTIONS, Scope::Table) {
+            self.error("no table section to close");
+            return;
+        }
+        self.clear_stack_back_to_table_body_context();
+        self.pop();
+        self.mode = InsertionMode::InTable;
+        self.process(token);
+    }
+
+    fn close_row(&mut self) -> bool {
+        if !self.in_scope(&["tr"], Scope::Table) {
+            self.error("no <tr> to close");
+            return false;
+        }
+        self.clear_stack_back_to_table_row_context();
+        self.pop();
+        self.mode = InsertionMode::InTableBody;
+        true
+    }
+
+    fn in_row(&mut self, token: Token) {
+        match token {
+            Token::StartTag(ref tag) if tag.name == "th" || tag.name == "td" => {
+                self.clear_stack_back_to_table_row_context();
+                self.insert_element(tag);
+                self.mode = InsertionMode::InCell;
+                self.active_formatting.push(FormattingEntry::Marker);
+            },
+            Token::EndTag(ref tag) if tag.name == "tr" => {
+                self.close_row();
+            },
+            Token::StartTag(ref tag) if ["caption", "col", "colgroup", "tbody", "tfoot", "thead",
+                                         "tr"].contains(&tag.name.as_str()) => {
+                if self.close_row() {
+                    self.process(token);
+                }
+            },
+            Token::EndTag(ref tag) if tag.name == "table" => {
+                if self.close_row() {
+                    self.process(token);
+                }
+            },
+            Token::EndTag(ref tag) if TABLE_SECTIONS.contains(&tag.name.as_str()) => {
+                if !self.in_scope(&[&tag.name], Scope::Table) {
+                    self.error(&format!("ignoring stray end tag </{}>", tag.name));
+                    return;
+                }
+                if self.close_row() {
+                    self.process(token);
+                }
+            },
+            Token::EndTag(ref tag) if ["body", "caption", "col", "colgroup", "html", "td",
+                                       "th"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            _ => self.in_table(token),
+        }
+    }
+
+    fn close_cell(&mut self) {
+        self.generate_implied_end_tags(None);
+        if !self.current_is(&["td", "th"]) {
+            self.error("closing a cell with open elements");
+        }
+        self.pop_until(&["td", "th"]);
+        self.clear_formatting_to_last_marker();
+        self.mode = InsertionMode::InRow;
+    }
+
+    fn in_cell(&mut self, token: Token) {
+        match token {
+            Token::EndTag(ref tag) if tag.name == "td" || tag.name == "th" => {
+                if !self.in_scope(&[&tag.name], Scope::Table) {
+                    self.error(&format!("ignoring stray end tag </{}>", tag.name));
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.current_is(&[&tag.name]) {
+                    self.error(&format!("</{}> implicitly closes open elements", tag.name));
+                }
+                self.pop_until(&[&tag.name]);
+                self.clear_formatting_to_last_marker();
+                self.mode = InsertionMode::InRow;
+            },
+            Token::StartTag(ref tag) if ["caption", "col", "colgroup", "tbody", "td", "tfoot", "th",
+                                         "thead", "tr"].contains(&tag.name.as_str()) => {
+                if !self.in_scope(&["td", "th"], Scope::Table) {
+                    self.error(&format!("ignoring <{}> outside of a cell", tag.name));
+                    return;
+                }
+                self.close_cell();
+                self.process(token);
+            },
+            Token::EndTag(ref tag) if ["body", "caption", "col", "colgroup",
+                                       "html"].contains(&tag.name.as_str()) => {
+                self.error(&format!("ignoring stray end tag </{}>", tag.name));
+            },
+            Token::EndTag(ref tag) if ["table", "tbody", "tfoot", "thead",
+                                       "tr"].contains(&tag.name.as_str()) => {
+                if !self.in_scope(&[&tag.name], Scope::Table) {
+                    self.error(&format!("ignoring stray end tag </{}>", tag.name));
+                    return;
+                }
+                self.close_cell();
+                self.process(token);
+            },
+            _ => self.in_body(token),
+        }
+    }
+
+    fn in_select(&mut self, token: Token) {
+        match token {
+            Token::Character('\0') => self.error("unexpected null character"),
+            Token::Character(c) => self.insert_character(c),
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) => match tag.name.as_str() {
+                "html" => self.in_body(token),
+                "option" => {
+                    if self.current_is(&["option"]) {
+                        self.pop();
+                    }
+                    self.insert_element(tag);
+                },
+                "optgroup" | "hr" => {
+                    if self.current_is(&["option"]) {
+                        self.pop();
+                    }
+                    if self.current_is(&["optgroup"]) {
+                        self.pop();
+                    }
+                    self.insert_element(tag);
+                    if tag.name == "hr" {
+                        self.pop();
+                    }
+                },
+                "select" => {
+                    self.error("nested <select>");
+                    if self.in_scope(&["select"], Scope::Select) {
+                        self.pop_until(&["select"]);
+                        self.reset_insertion_mode();
+                    }
+                },
+                "input" | "keygen" | "textarea" => {
+                    self.error(&format!("<{}> inside a <select>", tag.name));
+                    if self.in_scope(&["select"], Scope::Select) {
+                        self.pop_until(&["select"]);
+                        self.reset_insertion_mode();
+                        self.process(token);
+                    }
+                },
+                "script" | "template" => self.in_head(token),
+                _ => self.error(&format!("ignoring <{}> inside a <select>", tag.name)),
+            },
+            Token::EndTag(ref tag) => match tag.name.as_str() {
+                "optgroup" => {
+                    let len = self.open_elements.len();
+                    if self.current_is(&["option"]) && len > 1 && self.is(self.open_elements[len - 2], &["optgroup"]) {
+                        self.pop();
+                    }
+                    if self.current_is(&["optgroup"]) {
+                        self.pop();
+                    } else {
+                        self.error("ignoring stray end tag </optgroup>");
+                    }
+                },
+                "option" => {
+                    if self.current_is(&["option"]) {
+                        self.pop();
+                    } else {
+                        self.error("ignoring stray end tag </option>");
+                    }
+                },
+                "select" => {
+                    if !self.in_scope(&["select"], Scope::Select) {
+                        self.error("ignoring stray end tag </select>");
+                        return;
+                    }
+                    self.pop_until(&["select"]);
+                    self.reset_insertion_mode();
+                },
+                "template" => self.in_head(token),
+                _ => self.error(&format!("ignoring stray end tag </{}>", tag.name)),
+            },
+        }
+    }
+
+    fn in_select_in_table(&mut self, token: Token) {
+        const TABLE_TAGS: &[&str] = &["caption", "table", "tbody", "td", "tfoot", "th", "thead", "tr"];
+        match token {
+            Token::StartTag(ref tag) if TABLE_TAGS.contains(&tag.name.as_str()) => {
+                self.error(&format!("<{}> inside a <select>", tag.name));
+                self.pop_until(&["select"]);
+                self.reset_insertion_mode();
+                self.process(token);
+            },
+            Token::EndTag(ref tag) if TABLE_TAGS.contains(&tag.name.as_str()) => {
+                self.error(&format!("</{}> inside a <select>", tag.name));
+                if self.in_scope(&[&tag.name], Scope::Table) {
+                    self.pop_until(&["select"]);
+                    self.reset_insertion_mode();
+                    self.process(token);
+                }
+            },
+            _ => self.in_select(token),
+        }
+    }
+
+    fn in_template(&mut self, token: Token) {
+        let mode = match &token {
+            Token::StartTag(tag) => match tag.name.as_str() {
+                "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes" | "script" | "style"
+                | "template" | "title" => return self.in_head(token),
+                "caption" | "colgroup" | "tbody" | "tfoot" | "thead" => InsertionMode::InTable,
+                "col" => InsertionMode::InColumnGroup,
+                "tr" => InsertionMode::InTableBody,
+                "td" | "th" => InsertionMode::InRow,
+                _ => InsertionMode::InBody,
+            },
+            Token::EndTag(tag) if tag.name == "template" => return self.in_head(token),
+            Token::EndTag(tag) => return self.error(&format!("ignoring stray end tag </{}>", tag.name)),
+            _ => return self.in_body(token),
+        };
+        // The first start tag decides how the content of the template is parsed.
+        self.template_modes.pop();
+        self.template_modes.push(mode);
+        self.mode = mode;
+        self.process(token);
+    }
+
+    fn after_body(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => self.in_body(token),
+            Token::Comment(text) => self.insert_comment(text, Some(self.open_elements[0])),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) if tag.name == "html" => self.in_body(token),
+            Token::EndTag(ref tag) if tag.name == "html" => self.mode = InsertionMode::AfterAfterBody,
+            _ => {
+                self.error("unexpected content after </body>");
+                self.mode = InsertionMode::InBody;
+                self.process(token);
+            },
+        }
+    }
+
+    fn after_after_body(&mut self, token: Token) {
+        match token {
+            Token::Comment(text) => self.insert_comment(text, Some(DOCUMENT)),
+            Token::Character(c) if is_whitespace(c) => self.in_body(token),
+            Token::Doctype(_) => self.in_body(token),
+            Token::StartTag(ref tag) if tag.name == "html" => self.in_body(token),
+            _ => {
+                self.error("unexpected content after </html>");
+                self.mode = InsertionMode::InBody;
+                self.process(token);
+            },
+        }
+    }
+
+    fn in_frameset(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => self.insert_character(c),
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) => match tag.name.as_str() {
+                "html" => self.in_body(token),
+                "frameset" => {
+                    self.insert_element(tag);
+                },
+                "frame" => {
+                    self.insert_element(tag);
+                    self.pop();
+                },
+                "noframes" => self.in_head(token),
+                _ => self.error(&format!("ignoring <{}> inside a <frameset>", tag.name)),
+            },
+            Token::EndTag(ref tag) if tag.name == "frameset" => {
+                if self.open_elements.len() == 1 {
+                    self.error("ignoring stray end tag </frameset>");
+                    return;
+                }
+                self.pop();
+                if self.context.is_none() && !self.current_is(&["frameset"]) {
+                    self.mode = InsertionMode::AfterFrameset;
+                }
+            },
+            _ => self.error("unexpected content in <frameset>"),
+        }
+    }
+
+    fn after_frameset(&mut self, token: Token) {
+        match token {
+            Token::Character(c) if is_whitespace(c) => self.insert_character(c),
+            Token::Comment(text) => self.insert_comment(text, None),
+            Token::Doctype(_) => self.error("unexpected doctype"),
+            Token::StartTag(ref tag) if tag.name == "html" => self.in_body(token),
+            Token::StartTag(ref tag) if tag.name == "noframes" => self.in_head(token),
+            Token::EndTag(ref tag) if tag.name == "html" => self.mode = InsertionMode::AfterAfterFrameset,
+            _ => self.error("unexpected content after </frameset>"),
+        }
+    }
+
+    fn after_after_frameset(&mut self, token: Token) {
+        match token {
+            Token::Comment(text) => self.insert_comment(text, Some(DOCUMENT)),
+            Token::Character(c) if is_whitespace(c) => self.in_body(token),
+            Token::Doctype(_) => self.in_body(token),
+            Token::StartTag(ref tag) if tag.name == "html" => self.in_body(token),
+            Token::StartTag(ref tag) if tag.name == "noframes" => self.in_head(token),
+            _ => self.error("unexpected content after </html>"),
+        }
+    }
+
+    // Processes the end of the file, found at `end`.
+    pub fn finish(&mut self, end: Location) {
+        self.span = Span::at(end);
+        if let Some(span) = &mut self.tree[DOCUMENT].span {
+            span.end = end;
+        }
+        match self.mode {
+            InsertionMode::Initial | InsertionMode::BeforeHtml | InsertionMode::BeforeHead
+            | InsertionMode::InHead | InsertionMode::AfterHead => {
+                // Create the implied <html>, <head> and <body> elements.
+                self.process(Token::StartTag(Tag { name: "body".to_string(), ..Tag::default() }));
+            },
+            InsertionMode::Text => {
+                self.error("unexpected end of file in text-only element");
+                self.pop();
+                self.mode = self.original_mode;
+                self.finish(end);
+            },
+            InsertionMode::InTableText => {
+                self.flush_pending_table_text();
+                self.finish(end);
+            },
+            InsertionMode::InFrameset => {
+                if self.open_elements.len() > 1 {
+                    self.error("<frameset> is not closed");
+                }
+            },
+            InsertionMode::AfterFrameset | InsertionMode::AfterAfterFrameset => {},
+            // Stops when parsing a fragment of a template.
+            _ if !self.template_modes.is_empty() => {
+                if self.template_is_open() {
+                    self.error("<template> is not closed");
+                    self.pop_until(&["template"]);
+                    self.clear_formatting_to_last_marker();
+                    self.template_modes.pop();
+                    self.reset_insertion_mode();
+                    self.finish(end);
+                }
+            },
+            _ => self.check_unclosed_elements(),
+        }
+    }
+
+    // Tree built so far, elements still open included.
+    pub fn tree(&self) -> &dom::Tree {
+        &self.tree
+    }
+
+    pub fn tree_mut(&mut self) -> &mut dom::Tree {
+        &mut self.tree
+    }
+
+    pub fn into_tree(self) -> dom::Tree {
+        self.tree
+    }
+
+    // Tree of a fragment builder, where the parsed nodes are moved from the
+    // <html> element to the root DocumentFragment.
+    pub fn into_fragment(mut self) -> dom::Tree {
+        let html = self.tree[DOCUMENT].first_child().unwrap();
+        while let Some(child) = self.tree[html].first_child() {
+            self.tree.insert_unchecked(DOCUMENT, child, Some(html));
+        }
+        self.tree.detach(html);
+        self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dom::{AttrMap, ElementData, Namespace, NodeRef, NodeType};
+    use crate::html::{inner_html, parse, parse_fragment, ParseError};
+
+    // Writes the children of the node in the format of the html5lib tree
+    // construction tests, one node per line, e.g. `| <p>` or `|   "text"`.
+    fn dump(node: NodeRef, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        for child in node.children() {
+            match &child.node_type {
+                NodeType::Doctype(doctype) => out.push_str(&format!("| {}<!DOCTYPE {}>\n", indent, doctype.name)),
+                NodeType::Comment(data) => out.push_str(&format!("| {}<!-- {} -->\n", indent, data)),
+                NodeType::Text(data) => out.push_str(&format!("| {}\"{}\"\n", indent, data)),
+                NodeType::Element(element) => {
+                    let prefix = match element.namespace {
+                        Namespace::Svg => "svg ",
+                        Namespace::MathMl => "math ",
+                        _ => "",
+                    };
+                    out.push_str(&format!("| {}<{}{}>\n", indent, prefix, element.tag_name));
+                    let mut attributes: Vec<_> = element.attributes().iter().collect();
+                    attributes.sort();
+                    for (name, value) in attributes {
+                        out.push_str(&format!("| {}  {}=\"{}\"\n", indent, name, value));
+                    }
+                },
+                NodeType::Document(_) | NodeType::DocumentFragment => (),
+            }
+            dump(child, depth + 1, out);
+        }
+    }
+
+    // Checks the dump of the children of `root` against the expected one, given
+    // without the indentation of the test source.
+    fn assert_dump(root: NodeRef, expected: &str, source: &str) {
+        let mut tree = String::new();
+        dump(root, 0, &mut tree);
+        let expected: String = expected.lines()
+                                       .map(str::trim_start)
+                                       .filter(|line| !line.is_empty())
+                                       .map(|line| format!("{}\n", line))
+                                       .collect();
+        assert_eq!(tree, expected, "tree of {:?}", source);
+    }
+
+    fn assert_tree(source: &str, expected: &str) {
+        let (document, _) = parse(source.to_string());
+        assert_dump(document.root(), expected, source);
+    }
+
+    // Like assert_tree, for a fragment parsed in a `context` element.
+    fn assert_fragment(context: &str, source: &str, expected: &str) {
+        let (fragment, _) = parse_fragment(source.to_string(), &ElementData::new(context.to_string(), AttrMap::new()));
+        assert_dump(fragment.root(), expected, source);
+    }
+
+    // Serialization of the <body> of the parsed document, with the parse errors.
+    fn parse_body(source: &str) -> (String, Vec<ParseError>) {
+        let (document, errors) = parse(source.to_string());
+        let body = document.root().get_elements_by_tag_name("body")[0];
+        (inner_html(body), errors)
+    }
+
+    #[test]
+    fn ruby_annotations_close_each_other() {
+        let (body, errors) = parse_body("<!DOCTYPE html><ruby>a<rt>b<rp>c</ruby>");
+        assert_eq!(body, "<ruby>a<rt>b</rt><rp>c</rp></ruby>");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (body, errors) = parse_body("<!DOCTYPE html><ruby>a<rb>b<rtc><rt>c<rp>d</ruby>");
+        assert_eq!(body, "<ruby>a<rb>b</rb><rtc><rt>c</rt><rp>d</rp></rtc></ruby>");
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn ruby_annotations_outside_of_ruby() {
+        let (body, errors) = parse_body("<!DOCTYPE html><ruby><span><rt>a</span></ruby>");
+        assert_eq!(body, "<ruby><span><rt>a</rt></span></ruby>");
+        assert!(!errors.is_empty());
+        // Without a <ruby> in scope they are inserted as they are.
+        let (body, _) = parse_body("<!DOCTYPE html><rt>a<rp>b");
+        assert_eq!(body, "<rt>a<rp>b</rp></rt>");
+    }
+
+    #[test]
+    fn document_structure_modes() {
+        assert_tree("<!DOCTYPE html><title>a</title>b", r#"
+            | <!DOCTYPE html>
+            | <html>
+            |   <head>
+            |     <title>
+            |       "a"
+            |   <body>
+            |     "b"
+        "#);
+        // Comments before <html>, after </head> and after </html>.
+        assert_tree("<!--a--><html><head></head><!--b--><body></body></html><!--c-->", r#"
+            | <!-- a -->
+            | <html>
+            |   <head>
+            |   <!-- b -->
+            |   <body>
+            | <!-- c -->
+        "#);
+        assert_tree("<head><meta charset=utf-8><script>a<b</script><style>p{}</style></head>", r#"
+            | <html>
+            |   <head>
+            |     <meta>
+            |       charset="utf-8"
+            |     <script>
+            |       "a<b"
+            |     <style>
+            |       "p{}"
+            |   <body>
+        "#);
+        // Text after </body> goes back in the body.
+        assert_tree("<body>a</body>b", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     "ab"
+        "#);
+    }
+
+    #[test]
+    fn in_body() {
+        assert_tree("<ul><li>a<li>b</ul><dl><dt>c<dd>d</dl>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <ul>
+            |       <li>
+            |         "a"
+            |       <li>
+            |         "b"
+            |     <dl>
+            |       <dt>
+            |         "c"
+            |       <dd>
+            |         "d"
+        "#);
+        assert_tree("<p>a<div>b</div><textarea>\nc</textarea>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <p>
+            |       "a"
+            |     <div>
+            |       "b"
+            |     <textarea>
+            |       "c"
+        "#);
+    }
+
+    #[test]
+    fn adoption_agency() {
+        assert_tree("<a>1<p>2</a>3</p>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <a>
+            |       "1"
+            |     <p>
+            |       <a>
+            |         "2"
+            |       "3"
+        "#);
+        assert_tree("<b>1<p>2</b>3</p>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <b>
+            |       "1"
+            |     <p>
+            |       <b>
+            |         "2"
+            |       "3"
+        "#);
+        // Formatting elements are reopened after the end of a block.
+        assert_tree("<p><b><i>a</p>b", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <p>
+            |       <b>
+            |         <i>
+            |           "a"
+            |     <b>
+            |       <i>
+            |         "b"
+        "#);
+    }
+
+    #[test]
+    fn tables_and_foster_parenting() {
+        assert_tree("<table>a<tr><td>b</td>c</tr></table>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     "ac"
+            |     <table>
+            |       <tbody>
+            |         <tr>
+            |           <td>
+            |             "b"
+        "#);
+        // Whitespace stays in the table.
+        assert_tree("<table> <col><tr>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <table>
+            |       " "
+            |       <colgroup>
+            |         <col>
+            |       <tbody>
+            |         <tr>
+        "#);
+        assert_tree("<table><caption>a<td>b</table>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <table>
+            |       <caption>
+            |         "a"
+            |       <tbody>
+            |         <tr>
+            |           <td>
+            |             "b"
+        "#);
+    }
+
+    #[test]
+    fn select_template_and_frameset_modes() {
+        assert_tree("<select><option>a<option>b<optgroup><option>c</select>d", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <select>
+            |       <option>
+            |         "a"
+            |       <option>
+            |         "b"
+            |       <optgroup>
+            |         <option>
+            |           "c"
+            |     "d"
+        "#);
+        assert_tree("<table><tr><td><select><td>a", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <table>
+            |       <tbody>
+            |         <tr>
+            |           <td>
+            |             <select>
+            |           <td>
+            |             "a"
+        "#);
+        assert_tree("<template><td>a</td></template>", r#"
+            | <html>
+            |   <head>
+            |     <template>
+            |       <td>
+            |         "a"
+            |   <body>
+        "#);
+        assert_tree("<frameset><frame></frameset>a", r#"
+            | <html>
+            |   <head>
+            |   <frameset>
+            |     <frame>
+        "#);
+    }
+
+    #[test]
+    fn foreign_content() {
+        assert_tree("<svg viewbox='0 0 1 1'><foreignobject><p>a</p></foreignobject><path/></svg><math><mi>b</mi></math>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <svg svg>
+            |       viewBox="0 0 1 1"
+            |       <svg foreignObject>
+            |         <p>
+            |           "a"
+            |       <svg path>
+            |     <math math>
+            |       <math mi>
+            |         "b"
+        "#);
+        // HTML elements break out of foreign content.
+        assert_tree("<svg><g><p>a", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <svg svg>
+            |       <svg g>
+            |     <p>
+            |       "a"
+        "#);
+        assert_tree("<svg><![CDATA[a<b]]></svg>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <svg svg>
+            |       "a<b"
+        "#);
+    }
+
+    #[test]
+    fn ruby_tree() {
+        assert_tree("<ruby>a<rt>b<rp>c</ruby>", r#"
+            | <html>
+            |   <head>
+            |   <body>
+            |     <ruby>
+            |       "a"
+            |       <rt>
+            |         "b"
+            |       <rp>
+            |         "c"
+        "#);
+    }
+
+    #[test]
+    fn fragments() {
+        assert_fragment("ul", "<li>a</li><li>b", r#"
+            | <li>
+            |   "a"
+            | <li>
+            |   "b"
+        "#);
+        // The context sets the insertion mode, so the cells are not dropped.
+        assert_fragment("tr", "<td>a<td>b</td>c", r#"
+            | <td>
+            |   "a"
+            | <td>
+            |   "b"
+            | "c"
+        "#);
+        assert_fragment("table", "<tr><td>a", r#"
+            | <tbody>
+            |   <tr>
+            |     <td>
+            |       "a"
+        "#);
+        // The start tags of the context are ignored like in a document.
+        assert_fragment("body", "<td>a</td><body id=x>", r#"
+            | "a"
+        "#);
+        assert_fragment("textarea", "<p>&amp;</textarea>", r#"
+            | "<p>&</textarea>"
+        "#);
+        assert_fragment("select", "<option>a<p>b<option>c", r#"
+            | <option>
+            |   "ab"
+            | <option>
+            |   "c"
+        "#);
+    }
+}
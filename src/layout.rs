@@ -74,14 +74,13 @@ impl LayoutBox<'_> {
         };
         LayoutBox{
             dimensions: Default::default(),
-            box_type: box_type,
-            styled_node: styled_node,
+            box_type,
+            styled_node,
             children: Vec::new(),
         }
     }
 
     fn compute_dimensions(&mut self, dim_parent: &Dimensions) {
-        println!("COMPUTE BLOCK DIMS: {:?}", self.box_type);
         //TODO is here ok to check if text?
         match &self.box_type {
             BoxType::BlockNode => self.compute_block_dimensions(dim_parent),
@@ -167,13 +166,13 @@ impl LayoutBox<'_> {
 
         let mut margin_left = style.get_property("margin-left").unwrap_or(&zero);
         let mut margin_right = style.get_property("margin-right").unwrap_or(&zero);
-        let border_left = style.get_property("border-left").unwrap_or(&zero);
-        let border_right = style.get_property("border-right").unwrap_or(&zero);
+        let border_left = style.border_width("left");
+        let border_right = style.border_width("right");
         let padding_left = style.get_property("padding-left").unwrap_or(&zero);
         let padding_right = style.get_property("padding-right").unwrap_or(&zero);
 
-        let total: f32 = [&margin_left, &margin_right, &border_left, &border_right,
-                     &padding_left, &padding_right, &width].iter().map(|l| l.to_px()).sum();
+        let total: f32 = [&margin_left, &margin_right, &padding_left, &padding_right, &width]
+                         .iter().map(|l| l.to_px()).sum::<f32>() + border_left + border_right;
 
         let underflow = dim_parent.content.width - total;
 
@@ -189,8 +188,8 @@ impl LayoutBox<'_> {
         dims.content.width = width.to_px();
         dims.padding.left = padding_left.to_px();
         dims.padding.right = padding_right.to_px();
-        dims.border.left = border_left.to_px();
-        dims.border.right = border_right.to_px();
+        dims.border.left = border_left;
+        dims.border.right = border_right;
         dims.margin.left = margin_left.to_px();
         dims.margin.right = margin_right.to_px();
 
@@ -229,8 +228,8 @@ impl LayoutBox<'_> {
 
         dims.margin.top = style.get_property("margin-top").unwrap_or(&zero).to_px();
         dims.margin.bottom = style.get_property("margin-bottom").unwrap_or(&zero).to_px();
-        dims.border.top = style.get_property("border-top").unwrap_or(&zero).to_px();
-        dims.border.bottom = style.get_property("border-bottom").unwrap_or(&zero).to_px();
+        dims.border.top = style.border_width("top");
+        dims.border.bottom = style.border_width("bottom");
         dims.padding.top = style.get_property("padding-top").unwrap_or(&zero).to_px();
         dims.padding.bottom = style.get_property("padding-bottom").unwrap_or(&zero).to_px();
 
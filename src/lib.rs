@@ -0,0 +1,8 @@
+pub mod source;
+pub mod encoding;
+pub mod dom;
+pub mod html;
+pub mod css;
+pub mod style;
+pub mod layout;
+pub mod render;
@@ -1,12 +1,19 @@
-mod dom;
-mod html;
-mod css;
-mod style;
-mod layout;
-mod render;
+use toy_browser_engine::{html, style, layout, render};
 
 fn main() {
     let html_doc = r#"
+<head>
+    <style>
+        body {
+          background-color:#80abAA;
+          letter-spacing:10.5px;
+        }
+        p {
+          font-style:italic;
+          font-family:times;
+        }
+    </style>
+</head>
 <body>
     <p toto = "tutu"  tata="lol" >
         test text node
@@ -15,24 +22,19 @@ fn main() {
 </body>
 "#.to_string();
 
-    let mut css_doc = r#"
-body {
-  background-color:#80abAA;
-  letter-spacing:10.5px;
-}
-p {
-  font-style:italic;
-  font-family:times;
-}
-"#.to_string();
-
-    let html_tree = html::parse(html_doc);
+    let (html_tree, errors) = html::parse(html_doc);
+    for error in &errors {
+        println!("HTML parse error at {}", error);
+    }
     println!("{}\n", html_tree);
 
-    let style_sheet = css::parse(&mut css_doc);
-    //println!("{:?}", style_sheet);
+    let (style_sheets, errors) = style::extract_style_sheets(html_tree.root());
+    for error in &errors {
+        println!("CSS parse error at {}", error);
+    }
+    //println!("{:?}", style_sheets);
 
-    let style_tree = style::build_style_tree(&html_tree, &style_sheet);
+    let style_tree = style::build_style_tree(html_tree.root(), &style_sheets);
     println!("{}\n", style_tree);
 
     let layout_tree = layout::build_layout_tree(&style_tree);
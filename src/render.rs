@@ -1,5 +1,4 @@
 use crate::layout;
-use crate::dom;
 use crate::css;
 
 extern crate find_folder;
@@ -19,7 +18,7 @@ pub fn render_layout_tree(layout_tree: &layout::LayoutBox) {
         window.draw_2d(&e, |_, g, _| {
             clear([0.5, 0.5, 0.5, 1.0], g);
         });
-        render_box(&mut window, &e, &layout_tree);
+        render_box(&mut window, &e, layout_tree);
     }
 }
 
@@ -0,0 +1,121 @@
+use std::fmt;
+
+// Position of a character in a source text. Lines and columns start at 1,
+// columns count characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn start() -> Location {
+        Location { offset: 0, line: 1, column: 1 }
+    }
+}
+
+// Part of a source text, from its first character to right after its last one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    pub fn at(location: Location) -> Span {
+        Span { start: location, end: location }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+// Byte offsets at which the lines of a text start, to turn offsets into locations.
+// The start of the text can be dropped once its locations are not needed anymore.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    // Starts of the lines from `first_line` on.
+    line_starts: Vec<usize>,
+    first_line: usize,
+    // Length of the text already looked at.
+    scanned: usize,
+    // Length of the start of the text dropped with `drain`, and its location.
+    drained: usize,
+    drained_end: Location,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut index = LineIndex {
+            line_starts: vec![0],
+            first_line: 1,
+            scanned: 0,
+            drained: 0,
+            drained_end: Location::start(),
+        };
+        index.update(text);
+        index
+    }
+
+    // Takes into account what was appended to the text since the last update.
+    // `text` is what is left of the text after `drain`.
+    pub fn update(&mut self, text: &str) {
+        let scanned = self.scanned;
+        let mut chars = text[scanned - self.drained..].char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            let offset = scanned + offset;
+            match (c, chars.peek()) {
+                ('\n', _) => self.line_starts.push(offset + 1),
+                // A lone '\r' and "\r\n" both end a line, which is only known
+                // once the next character is there.
+                ('\r', None) => {
+                    self.scanned = offset;
+                    return;
+                },
+                ('\r', Some(&(_, next))) if next != '\n' => self.line_starts.push(offset + 1),
+                _ => {},
+            }
+        }
+        self.scanned = self.drained + text.len();
+    }
+
+    // `text` must be the one the index was built from, without its drained
+    // start. Offsets are in the whole text, and must not be in the drained part.
+    pub fn location(&self, text: &str, offset: usize) -> Location {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        // The columns of the line holding the drained end are counted from there.
+        let (start, start_column) = if line_start < self.drained {
+            (self.drained, self.drained_end.column)
+        } else {
+            (line_start, 1)
+        };
+        let column = text.get(start - self.drained..offset.saturating_sub(self.drained))
+                         .map_or(0, |before| before.chars().count()) + start_column;
+        Location { offset, line: self.first_line + line - 1, column }
+    }
+
+    // Drops up to `len` bytes from the start of `text`, keeping what is needed
+    // for the locations after them. Returns the number of bytes dropped.
+    pub fn drain(&mut self, text: &mut String, len: usize) -> usize {
+        let len = len.min(self.scanned - self.drained);
+        let end = self.drained + len;
+        self.drained_end = self.location(text, end);
+        text.drain(..len);
+        self.drained = end;
+        let line = self.line_starts.partition_point(|&start| start <= end);
+        self.line_starts.drain(..line - 1);
+        self.first_line += line - 1;
+        len
+    }
+}
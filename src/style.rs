@@ -2,12 +2,13 @@ use std::fmt;
 
 use crate::dom;
 use crate::css;
+use crate::source;
 
 use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct StyledNode<'a> {
-    pub html_node: &'a dom::Node,
+    pub html_node: dom::NodeRef<'a>,
     properties: Properties,
     pub children: Vec<StyledNode<'a>>,
 }
@@ -24,93 +25,460 @@ impl StyledNode<'_> {
         self.properties.get(prop)
     }
 
+    // Used width of the border of a side, e.g. "left". There is no border
+    // without a style.
+    pub fn border_width(&self, side: &str) -> f32 {
+        match self.get_property(&format!("border-{}-style", side)) {
+            Some(css::Value::Keyword(style)) if style != "none" && style != "hidden" => (),
+            _ => return 0.0,
+        }
+        match self.get_property(&format!("border-{}-width", side)) {
+            Some(css::Value::Keyword(width)) if width == "thin" => 1.0,
+            Some(css::Value::Keyword(width)) if width == "thick" => 5.0,
+            Some(css::Value::Keyword(_)) | None => 3.0,
+            Some(width) => width.to_px(),
+        }
+    }
+
     pub fn get_display(&self) -> Display {
-        match self.html_node.node_type {
+        match &self.html_node.node_type {
+            // Whitespace between elements does not generate any box.
+            dom::NodeType::Text(text) if text.trim().is_empty() => Display::None,
             dom::NodeType::Text(_) => Display::Text,
-            _ => match self.get_property("display") {
+            dom::NodeType::Element(element) => match self.get_property("display") {
                 Some(css::Value::Keyword(val)) => match val.as_str() {
                     "block" => Display::Block,
                     "none" => Display::None,
                     //TODO default should be inline or block?
                     _ => Display::Block,   
                 },
+                _ if HIDDEN_ELEMENTS.contains(&element.tag_name.as_str()) => Display::None,
                 //TODO default should be inline or block?
                 _ => Display::Block,
             },
+            dom::NodeType::Document(_) | dom::NodeType::DocumentFragment => Display::Block,
+            dom::NodeType::Doctype(_) | dom::NodeType::Comment(_) => Display::None,
+        }
+    }
+}
+
+// Pieces of the rendered text of a node, see
+// https://html.spec.whatwg.org/multipage/dom.html#the-innertext-idl-attribute
+enum TextItem {
+    // Text, with whether its whitespace collapses.
+    Text(String, bool),
+    // Newline of a <br>.
+    LineBreak,
+    // Newlines around blocks, only kept between texts.
+    RequiredLineBreaks(usize),
+}
+
+// How an element breaks the rendered text.
+enum TextDisplay {
+    Inline,
+    Block,
+    Paragraph,
+    TableRow,
+    TableCell,
+}
+
+impl StyledNode<'_> {
+    // Text of the node as rendered: hidden nodes are left out, blocks are on
+    // their own lines and whitespace collapses outside of <pre>.
+    fn inner_text(&self) -> String {
+        let mut items = Vec::new();
+        self.collect_text(false, &mut items);
+        join_text_items(items)
+    }
+
+    fn text_display(&self, element: &dom::ElementData) -> TextDisplay {
+        match self.get_property("display") {
+            Some(css::Value::Keyword(display)) => match display.as_str() {
+                "block" | "list-item" | "flex" | "grid" | "table" | "table-caption" => TextDisplay::Block,
+                "table-row" => TextDisplay::TableRow,
+                "table-cell" => TextDisplay::TableCell,
+                _ => TextDisplay::Inline,
+            },
+            // Layout does not have inline boxes yet, so the default display
+            // of HTML elements decides which ones break lines.
+            _ if !element.is_html() => TextDisplay::Inline,
+            _ => match element.tag_name.as_str() {
+                "p" => TextDisplay::Paragraph,
+                "tr" => TextDisplay::TableRow,
+                "td" | "th" => TextDisplay::TableCell,
+                name if BLOCK_ELEMENTS.contains(&name) => TextDisplay::Block,
+                _ => TextDisplay::Inline,
+            },
+        }
+    }
+
+    fn collect_text(&self, preserve_whitespace: bool, items: &mut Vec<TextItem>) {
+        let element = match &self.html_node.node_type {
+            dom::NodeType::Text(text) => {
+                items.push(TextItem::Text(text.clone(), !preserve_whitespace));
+                return;
+            },
+            dom::NodeType::Element(element) => element,
+            dom::NodeType::Document(_) | dom::NodeType::DocumentFragment => {
+                for child in &self.children {
+                    child.collect_text(preserve_whitespace, items);
+                }
+                return;
+            },
+            dom::NodeType::Doctype(_) | dom::NodeType::Comment(_) => return,
+        };
+        if let Display::None = self.get_display() {
+            return;
+        }
+        if element.is_html() && element.tag_name == "br" {
+            items.push(TextItem::LineBreak);
+            return;
         }
+        let preserve_whitespace = preserve_whitespace
+            || element.is_html() && PREFORMATTED_ELEMENTS.contains(&element.tag_name.as_str());
+        let display = self.text_display(element);
+        let breaks = match display {
+            TextDisplay::Block => 1,
+            TextDisplay::Paragraph => 2,
+            _ => 0,
+        };
+        items.push(TextItem::RequiredLineBreaks(breaks));
+        for child in &self.children {
+            child.collect_text(preserve_whitespace, items);
+        }
+        items.push(TextItem::RequiredLineBreaks(breaks));
+        // Cells are separated by tabs and rows by newlines.
+        let last = self.html_node.following_siblings().all(|sibling| sibling.element().is_none());
+        match display {
+            TextDisplay::TableCell if !last => items.push(TextItem::Text("\t".to_string(), false)),
+            TextDisplay::TableRow if !last => items.push(TextItem::Text("\n".to_string(), false)),
+            _ => (),
+        }
+    }
+}
+
+// Replaces runs of whitespace by a single space.
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !collapsed.ends_with(' ') {
+                collapsed.push(' ');
+            }
+        } else {
+            collapsed.push(c);
+        }
+    }
+    collapsed
+}
+
+fn join_text_items(items: Vec<TextItem>) -> String {
+    let mut text = String::new();
+    // Collapsible space at the end of the text, removed at the end of a line.
+    let mut trailing_space = false;
+    let mut required_line_breaks = 0;
+    for item in items {
+        let (mut piece, collapsible) = match item {
+            TextItem::RequiredLineBreaks(count) => {
+                required_line_breaks = required_line_breaks.max(count);
+                continue;
+            },
+            TextItem::LineBreak => {
+                if trailing_space {
+                    text.pop();
+                }
+                ("\n".to_string(), false)
+            },
+            TextItem::Text(piece, true) => (collapse_whitespace(&piece), true),
+            TextItem::Text(piece, false) => (piece, false),
+        };
+        let line_start = text.is_empty() || required_line_breaks > 0 || text.ends_with('\n') || trailing_space;
+        if collapsible && line_start && piece.starts_with(' ') {
+            piece.remove(0);
+        }
+        if piece.is_empty() {
+            continue;
+        }
+        if required_line_breaks > 0 && !text.is_empty() {
+            if trailing_space {
+                text.pop();
+            }
+            text.extend(std::iter::repeat_n('\n', required_line_breaks));
+        }
+        required_line_breaks = 0;
+        text.push_str(&piece);
+        trailing_space = collapsible && piece.ends_with(' ');
+    }
+    if trailing_space {
+        text.pop();
+    }
+    text
+}
+
+// Text of a node under the root of the style tree as rendered, or its text
+// content if it is not rendered, e.g. in a display:none subtree.
+pub fn inner_text(style_tree: &StyledNode, node: dom::NodeRef) -> String {
+    let mut path: Vec<dom::NodeId> = std::iter::once(node).chain(node.ancestors())
+                                                          .map(|node| node.id())
+                                                          .take_while(|&id| id != style_tree.html_node.id())
+                                                          .collect();
+    let mut styled = Some(style_tree);
+    let mut rendered = true;
+    while let Some(current) = styled {
+        rendered = rendered && !matches!(current.get_display(), Display::None);
+        match path.pop() {
+            Some(id) => styled = current.children.iter().find(|child| child.html_node.id() == id),
+            None => break,
+        }
+    }
+    match styled {
+        Some(styled) if rendered => styled.inner_text(),
+        _ => node.text_content().unwrap_or_default(),
+    }
+}
+
+impl dom::NodeRef<'_> {
+    // Text of the node as rendered with the style sheets of its document, e.g.
+    // from extract_style_sheets. This builds the style tree of the document on
+    // each call, the inner_text function takes one that is already built.
+    pub fn inner_text(&self, sheets: &[css::StyleSheet]) -> String {
+        let style_tree = build_style_tree(self.tree().root(), sheets);
+        inner_text(&style_tree, *self)
     }
 }
 
 type Properties = HashMap<String, css::Value>;
 
-fn simple_selector_matches_node(sel: &css::SimpleSelector, node: &dom::ElementData) -> bool {
-    if sel.tag_name.iter().any(|sel_tag| &node.tag_name != sel_tag) {
+// Elements which are not rendered unless a stylesheet says otherwise.
+const HIDDEN_ELEMENTS: &[&str] = &["head", "link", "meta", "noscript", "script", "style", "title"];
+
+// Elements displayed as blocks by default.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "caption", "center", "dd", "details",
+    "dialog", "dir", "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form",
+    "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup", "hr", "html", "legend", "li",
+    "listing", "main", "menu", "nav", "ol", "plaintext", "pre", "section", "summary", "table",
+    "ul", "xmp",
+];
+
+// Elements whose whitespace is kept as is.
+const PREFORMATTED_ELEMENTS: &[&str] = &["listing", "plaintext", "pre", "textarea", "xmp"];
+
+// In quirks mode, ids and classes are matched case-insensitively.
+fn names_match(a: &str, b: &str, quirks_mode: dom::QuirksMode) -> bool {
+    match quirks_mode {
+        dom::QuirksMode::Quirks => a.eq_ignore_ascii_case(b),
+        _ => a == b,
+    }
+}
+
+fn simple_selector_matches_node(sel: &css::SimpleSelector, node: &dom::ElementData,
+                                quirks_mode: dom::QuirksMode) -> bool {
+    // Tag names of HTML elements are case-insensitive, the ones of SVG
+    // elements like `clipPath` are not.
+    if sel.tag_name.iter().any(|sel_tag| if node.is_html() { !node.tag_name.eq_ignore_ascii_case(sel_tag) } else { &node.tag_name != sel_tag }) {
         return false;
     }
-    if sel.id.iter().any(|sel_id| node.id() != Some(sel_id)) {
+    if sel.id.iter().any(|sel_id| !node.id().is_some_and(|id| names_match(id, sel_id, quirks_mode))) {
         return false;
     }
-    if sel.classes.iter().any(|sel_class| !node.classes().contains(sel_class.as_str())) {
+    let classes = node.classes();
+    if sel.classes.iter().any(|sel_class| !classes.iter().any(|class| names_match(class, sel_class, quirks_mode))) {
         return false;
     }
-    return true;
+    true
 }
 
-fn selectors_match_node(selectors: &Vec<css::Selector>, node: &dom::ElementData) -> bool {
-    for sel in selectors {
-        let res = match sel {
-            css::Selector::Simple(sel) => simple_selector_matches_node(sel, &node)
-        };
-        if res { return true; }
+// Whether an element matches the selector. Combinators look at the element's
+// ancestors and previous siblings in its tree.
+pub fn selector_matches(selector: &css::Selector, node: dom::NodeRef, quirks_mode: dom::QuirksMode) -> bool {
+    let element = match node.element() {
+        Some(element) => element,
+        None => return false,
+    };
+    match selector {
+        css::Selector::Simple(sel) => simple_selector_matches_node(sel, element, quirks_mode),
+        css::Selector::Complex(left, combinator, right) => {
+            if !simple_selector_matches_node(right, element, quirks_mode) {
+                return false;
+            }
+            let mut previous_element_siblings = std::iter::successors(node.previous_sibling(), |sibling| sibling.previous_sibling())
+                .filter(|sibling| sibling.element().is_some());
+            match combinator {
+                css::Combinator::Descendant => std::iter::successors(node.parent(), |parent| parent.parent())
+                    .any(|ancestor| selector_matches(left, ancestor, quirks_mode)),
+                css::Combinator::Child => node.parent().is_some_and(|parent| selector_matches(left, parent, quirks_mode)),
+                css::Combinator::NextSibling => previous_element_siblings.next()
+                    .is_some_and(|sibling| selector_matches(left, sibling, quirks_mode)),
+                css::Combinator::SubsequentSibling => previous_element_siblings
+                    .any(|sibling| selector_matches(left, sibling, quirks_mode)),
+            }
+        },
+    }
+}
+
+pub fn selectors_match(selectors: &[css::Selector], node: dom::NodeRef, quirks_mode: dom::QuirksMode) -> bool {
+    selectors.iter().any(|sel| selector_matches(sel, node, quirks_mode))
+}
+
+// Error of the selector queries, named after the DOM exception, when the
+// selector string could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyntaxError;
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the selector is invalid")
     }
-    return false;
 }
 
-fn get_matching_rules<'a>(node: &dom::ElementData, sheet: &'a css::StyleSheet) -> Vec<&'a css::Rule> {
-    sheet.rules.iter()
-               .filter(|rule| selectors_match_node(&rule.selectors, &node))
-               .collect()
+// Defined here rather than in dom, which does not depend on css.
+impl<'a> dom::NodeRef<'a> {
+    // First descendant element matching a comma-separated list of selectors.
+    pub fn query_selector(&self, selectors: &str) -> Result<Option<dom::NodeRef<'a>>, SyntaxError> {
+        let selectors = css::parse_selector_list(selectors).ok_or(SyntaxError)?;
+        let quirks_mode = self.tree().quirks_mode();
+        Ok(self.descendants().find(|node| selectors_match(&selectors, *node, quirks_mode)))
+    }
+
+    // All the descendant elements matching a comma-separated list of selectors.
+    pub fn query_selector_all(&self, selectors: &str) -> Result<Vec<dom::NodeRef<'a>>, SyntaxError> {
+        let selectors = css::parse_selector_list(selectors).ok_or(SyntaxError)?;
+        let quirks_mode = self.tree().quirks_mode();
+        Ok(self.descendants().filter(|node| selectors_match(&selectors, *node, quirks_mode)).collect())
+    }
+}
+
+// Also here so that dom does not depend on css.
+impl dom::ElementData {
+    // Declarations of the style attribute. Invalid ones are left out.
+    pub fn inline_style(&self) -> Vec<css::Declaration> {
+        match self.get_attribute("style") {
+            Some(style) => css::parse_declaration_list(style).0,
+            None => Vec::new(),
+        }
+    }
 }
 
-fn get_matching_properties(node: &dom::ElementData, sheet: &css::StyleSheet) -> Properties {
-    let matching_rules = get_matching_rules(node, sheet);
+// Rules matching the node in source order, with the specificity of their most
+// specific selector matching it.
+fn get_matching_rules<'a>(node: dom::NodeRef, sheets: &'a [css::StyleSheet],
+                          quirks_mode: dom::QuirksMode) -> Vec<(css::Specificity, &'a css::Rule)> {
+    sheets.iter()
+          .flat_map(|sheet| sheet.rules.iter())
+          .filter_map(|rule| {
+              rule.selectors.iter()
+                            .filter(|sel| selector_matches(sel, node, quirks_mode))
+                            .map(css::Selector::specificity)
+                            .max()
+                            .map(|specificity| (specificity, rule))
+          })
+          .collect()
+}
+
+fn get_matching_properties(node: dom::NodeRef, element: &dom::ElementData, sheets: &[css::StyleSheet],
+                           quirks_mode: dom::QuirksMode) -> Properties {
+    let mut matching_rules = get_matching_rules(node, sheets, quirks_mode);
+    // The sort is stable, so rules of the same specificity stay in source order.
+    matching_rules.sort_by_key(|(specificity, _)| *specificity);
+    let inline_declarations = element.inline_style();
     let mut ret_properties = HashMap::new();
-    for rule in matching_rules {
-        for declaration in &rule.declarations {
-            ret_properties.insert(declaration.name.clone(), declaration.value.clone()); 
+    // From lowest to highest precedence: author rules by specificity, style
+    // attribute, !important author rules by specificity, !important style attribute.
+    for important in [false, true] {
+        let declarations = matching_rules.iter()
+                                         .flat_map(|(_, rule)| rule.declarations.iter())
+                                         .chain(inline_declarations.iter());
+        for declaration in declarations.filter(|declaration| declaration.important == important) {
+            ret_properties.insert(declaration.name.clone(), declaration.value.clone());
         }
     }
     ret_properties
 }
 
-pub fn build_style_tree<'a>(html_node: &'a dom::Node, sheet: &'a css::StyleSheet) -> StyledNode<'a> {
+// Styled node without its children.
+fn style_node<'a>(html_node: dom::NodeRef<'a>, sheets: &[css::StyleSheet],
+                  quirks_mode: dom::QuirksMode) -> StyledNode<'a> {
     StyledNode{
-        html_node: html_node,
-        properties: match &html_node.node_type {
-            dom::NodeType::Element(element_data) => get_matching_properties(element_data, sheet),
-            dom::NodeType::Text(_) => HashMap::new(),
+        html_node,
+        properties: match html_node.element() {
+            Some(element_data) => get_matching_properties(html_node, element_data, sheets, quirks_mode),
+            None => HashMap::new(),
         },
-        children: html_node.children.iter()
-                                    .map(|child| build_style_tree(child, sheet))
-                                    .collect(),
+        children: Vec::new(),
     }
 }
 
+fn build_styled_node<'a>(html_node: dom::NodeRef<'a>, sheets: &'a [css::StyleSheet],
+                         quirks_mode: dom::QuirksMode) -> StyledNode<'a> {
+    StyledNode{
+        children: html_node.children()
+                           .map(|child| build_styled_node(child, sheets, quirks_mode))
+                           .collect(),
+        ..style_node(html_node, sheets, quirks_mode)
+    }
+}
+
+// Rules of later style sheets take precedence over the ones of earlier sheets.
+pub fn build_style_tree<'a>(html_node: dom::NodeRef<'a>, sheets: &'a [css::StyleSheet]) -> StyledNode<'a> {
+    let quirks_mode = html_node.tree().quirks_mode();
+    build_styled_node(html_node, sheets, quirks_mode)
+}
+
+fn collect_style_sheets(node: dom::NodeRef, sheets: &mut Vec<css::StyleSheet>, errors: &mut Vec<css::ParseError>) {
+    match node.element() {
+        Some(element) if element.tag_name == "style" => {
+            let source: String = node.children()
+                                         .filter_map(|child| match &child.node().node_type {
+                                             dom::NodeType::Text(text) => Some(text.as_str()),
+                                             _ => None,
+                                         })
+                                         .collect();
+            let start = node.first_child()
+                            .and_then(|child| child.span)
+                            .map_or(source::Location::start(), |span| span.start);
+            let (sheet, mut sheet_errors) = css::parse_at(&source, start);
+            sheets.push(sheet);
+            errors.append(&mut sheet_errors);
+        },
+        _ => {
+            for child in node.children() {
+                collect_style_sheets(child, sheets, errors);
+            }
+        },
+    }
+}
+
+// Parses the <style> elements of the document, in document order, with the
+// errors found in them.
+pub fn extract_style_sheets(html_node: dom::NodeRef) -> (Vec<css::StyleSheet>, Vec<css::ParseError>) {
+    let mut sheets = Vec::new();
+    let mut errors = Vec::new();
+    collect_style_sheets(html_node, &mut sheets, &mut errors);
+    (sheets, errors)
+}
+
 impl fmt::Display for StyledNode<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fn pretty_fmt(node: &StyledNode, prefix: String, f: &mut fmt::Formatter) -> fmt::Result {
             match &node.html_node.node_type {
                 dom::NodeType::Text(text) => {
-                    let to_write = if text.len() > 50 {text[..50].to_string()} else {text.clone()};
+                    let to_write: String = text.chars().take(50).collect();
                     writeln!(f, "{}{}", prefix, to_write)?;
                 },
+                dom::NodeType::Document(_) => writeln!(f, "{}#document", prefix)?,
+                dom::NodeType::DocumentFragment => writeln!(f, "{}#document-fragment", prefix)?,
+                dom::NodeType::Doctype(_) | dom::NodeType::Comment(_) => {},
                 dom::NodeType::Element(dom::ElementData{tag_name, ..}) => {
                     let mut str_properties = "".to_string();
                     for (name, value) in &node.properties {
                         if !str_properties.is_empty() {str_properties.push_str(", ")}
                         str_properties.push_str(&format!("{}={:?}", name, value))
                     }
-                    writeln!(f, "{}{} ({})", prefix, tag_name, str_properties)?;
+                    match node.html_node.span {
+                        Some(span) => writeln!(f, "{}{} ({}) at {}", prefix, tag_name, str_properties, span.start)?,
+                        None => writeln!(f, "{}{} ({})", prefix, tag_name, str_properties)?,
+                    }
                 },
             }
             for child in &node.children {
@@ -120,7 +488,44 @@ impl fmt::Display for StyledNode<'_> {
             }
             Ok(())
         }
-        pretty_fmt(&self, "".to_string(), f)?;
+        pretty_fmt(self, "".to_string(), f)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_style_sheets, SyntaxError};
+    use crate::html;
+
+    #[test]
+    fn query_selector_finds_elements_in_document_order() {
+        let (document, _) = html::parse("<ul><li class=a>1<li id=b>2<li class=a>3</ul><p class=a>4".to_string());
+        let root = document.root();
+        let text = |node: crate::dom::NodeRef| node.text_content().unwrap();
+        let first = root.query_selector("#b, ul > .a").unwrap().unwrap();
+        assert_eq!(text(first), "1");
+        let all: Vec<String> = root.query_selector_all("li.a, p").unwrap().into_iter().map(text).collect();
+        assert_eq!(all, ["1", "3", "4"]);
+        assert!(root.query_selector("div").unwrap().is_none());
+    }
+
+    #[test]
+    fn query_selector_rejects_invalid_selectors() {
+        let (document, _) = html::parse("<p>".to_string());
+        assert_eq!(document.root().query_selector("p >").unwrap_err(), SyntaxError);
+        assert_eq!(document.root().query_selector_all("").unwrap_err(), SyntaxError);
+    }
+
+    #[test]
+    fn inner_text_follows_the_style_sheets() {
+        let (document, _) = html::parse("<style>.hidden { display: none }</style>\
+                                         <div id=d>a <span class=hidden>b</span> c<p>d</p><pre> e  f </pre></div>".to_string());
+        let (sheets, _) = extract_style_sheets(document.root());
+        let div = document.root().query_selector("#d").unwrap().unwrap();
+        assert_eq!(div.inner_text(&sheets), "a c\n\nd\n\n e  f ");
+        // Hidden nodes fall back to their text content.
+        let span = document.root().query_selector(".hidden").unwrap().unwrap();
+        assert_eq!(span.inner_text(&sheets), "b");
+    }
+}